@@ -0,0 +1,36 @@
+// Plain JS because the frontend has no Node type definitions; see build-errors.d.ts
+import { mkdirSync, rmSync, writeFileSync } from "node:fs";
+import { join, resolve } from "node:path";
+
+// Must match overlay::BUILD_ERROR_FILE on the Rust side
+const BUILD_ERROR_FILE = "build-error.json";
+
+// Records a failed build in the output directory so the Rust app can show it in
+// its error overlay; the file is removed again when the next build starts
+export default function buildErrors() {
+  let outDir = "dist";
+
+  return {
+    name: "build-errors",
+    configResolved(config) {
+      outDir = resolve(config.root, config.build.outDir);
+    },
+    buildStart() {
+      rmSync(join(outDir, BUILD_ERROR_FILE), { force: true });
+    },
+    buildEnd(error) {
+      if (!error) return;
+
+      const report = {
+        source: "Vite build",
+        file: error.loc?.file ?? error.id ?? null,
+        line: error.loc?.line ?? null,
+        column: error.loc?.column ?? null,
+        message: error.message,
+        stack: error.frame ?? error.stack ?? null,
+      };
+      mkdirSync(outDir, { recursive: true });
+      writeFileSync(join(outDir, BUILD_ERROR_FILE), JSON.stringify(report, null, 2));
+    },
+  };
+}
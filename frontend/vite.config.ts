@@ -1,32 +1,23 @@
 import { defineConfig, Plugin } from "vite";
 import react from "@vitejs/plugin-react";
+import buildErrors from "./plugins/build-errors.js";
 
 function customAssetsPlugin(): Plugin {
   return {
     name: "custom-assets-protocol",
     enforce: "post",
     generateBundle(_options, bundle) {
-      // const isWatch = process.argv.includes("--watch");
-
       // Rewrite asset references in HTML files
       for (const fileName in bundle) {
         const chunk = bundle[fileName];
         if (chunk.type === "asset" && fileName.endsWith(".html")) {
           let html = chunk.source as string;
 
-          // Inject timestamp header if in watch mode
-          // if (isWatch) {
-          const timestamp = new Date().toISOString();
-          const timeHeader = `
-              <script>
-                console.log("Build timestamp: ${timestamp}");
-              </script>
-              <meta name="build-timestamp" content="${timestamp}">
-            `;
-
-          // Insert after opening head tag
-          html = html.replace(/<head>/i, `<head>${timeHeader}`);
-          // }
+          // The Rust AssetManager injects entry scripts, styles and preloads
+          // from manifest.json, so drop the tags Vite generated for them
+          html = html
+            .replace(/\s*<script type="module"[^>]*src="[^"]*\/assets\/[^"]*"[^>]*><\/script>/g, "")
+            .replace(/\s*<link rel="(stylesheet|modulepreload)"[^>]*href="[^"]*\/assets\/[^"]*"[^>]*>/g, "");
 
           // Rewrite asset paths
           chunk.source = html.replace(
@@ -57,32 +48,25 @@ export default defineConfig({
       "Cross-Origin-Embedder-Policy": "require-corp",
     },
   },
-  plugins: [react(), customAssetsPlugin()],
+  plugins: [react(), customAssetsPlugin(), buildErrors()],
   base: "/",
   build: {
     outDir: "dist",
     emptyOutDir: true,
+    // Lets the Rust side map entry points to hashed chunk names
+    manifest: true,
     rollupOptions: {
       output: {
-        // Ensure deterministic file names
-        entryFileNames: "assets/index.js",
-        chunkFileNames: "assets/chunk.[name].js",
-        assetFileNames: (assetInfo) => {
-          // Handle CSS files specially
-          if (assetInfo.name?.endsWith(".css")) {
-            return "assets/style.css";
-          }
-          // Other assets get predictable names
-          return "assets/[name].[ext]";
-        },
+        entryFileNames: "assets/[name]-[hash].js",
+        chunkFileNames: "assets/[name]-[hash].js",
+        assetFileNames: "assets/[name]-[hash][extname]",
       },
     },
-    // Disable chunk splitting
     target: "esnext",
     sourcemap: false,
     minify: "esbuild",
     cssMinify: true,
-    cssCodeSplit: false,
+    cssCodeSplit: true,
     watch: {
       // Enable watch mode when --watch flag is present
       include: ["src/**", "assets/**"],
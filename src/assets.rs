@@ -0,0 +1,185 @@
+use std::{
+    env, fs,
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use crate::{
+    compression::{self, CompressionCache, EncodedAsset, Encoding},
+    manifest::{ViteManifest, MANIFEST_PATHS},
+    security,
+};
+
+// Manifest key of the HTML entry point, and the URL prefix its files are served under
+pub const HTML_ENTRY: &str = "index.html";
+pub const ASSETS_URL: &str = "assets://";
+
+pub struct AssetManager {
+    base_path: PathBuf,
+    compression: CompressionCache,
+    manifest: Mutex<Option<(SystemTime, Arc<ViteManifest>)>>,
+}
+
+impl AssetManager {
+    pub fn new() -> Result<Self, ()> {
+        // Get the executable's directory
+        let exe_dir = env::current_exe()
+            .map_err(|_| ())?
+            .parent()
+            .ok_or(())?
+            .to_path_buf();
+
+        // In development, use the frontend/dist directory
+        let dev_path = Path::new("frontend/dist");
+
+        // Check if we're running in development or production
+        let base_path = if dev_path.exists() {
+            dev_path.to_path_buf()
+        } else {
+            // In production, look for assets in a directory next to the executable
+            exe_dir.join("assets")
+        };
+
+        Ok(Self::with_base_path(base_path))
+    }
+
+    pub fn with_base_path(base_path: PathBuf) -> Self {
+        Self {
+            base_path,
+            compression: CompressionCache::new(),
+            manifest: Mutex::new(None),
+        }
+    }
+
+    pub fn base_path(&self) -> &Path {
+        &self.base_path
+    }
+
+    pub fn resolve(&self, relative_path: &str) -> Result<PathBuf, std::io::Error> {
+        let relative = Path::new(relative_path.trim_start_matches('/'));
+
+        // Never let a request escape the asset directory
+        if relative
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)))
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                format!("Refusing to serve {}", relative_path),
+            ));
+        }
+
+        Ok(self.base_path.join(relative))
+    }
+
+    pub fn load_asset(&self, relative_path: &str) -> Result<Vec<u8>, std::io::Error> {
+        let path = self.resolve(relative_path)?;
+        fs::read(&path)
+    }
+
+    pub fn load_encoded_asset(
+        &self,
+        relative_path: &str,
+        content_type: &str,
+        accepted: &[Encoding],
+    ) -> Result<EncodedAsset, std::io::Error> {
+        let path = self.resolve(relative_path)?;
+        self.compression.load(&path, content_type, accepted)
+    }
+
+    // What load_encoded_asset would serve, without reading the file
+    pub fn negotiate_encoding(
+        &self,
+        relative_path: &str,
+        content_type: &str,
+        accepted: &[Encoding],
+    ) -> Result<(fs::Metadata, Option<Encoding>), std::io::Error> {
+        let path = self.resolve(relative_path)?;
+        let metadata = fs::metadata(&path)?;
+        let encoding = compression::negotiate(&path, &metadata, content_type, accepted);
+        Ok((metadata, encoding))
+    }
+
+    pub fn metadata(&self, relative_path: &str) -> Result<fs::Metadata, std::io::Error> {
+        fs::metadata(self.resolve(relative_path)?)
+    }
+
+    // The manifest's mtime, which changes with every build
+    pub fn manifest_modified(&self) -> Option<SystemTime> {
+        let path = self.manifest_path()?;
+        fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+
+    fn manifest_path(&self) -> Option<PathBuf> {
+        MANIFEST_PATHS
+            .iter()
+            .map(|relative| self.base_path.join(relative))
+            .find(|path| path.exists())
+    }
+
+    // Re-read whenever the build rewrites it, since every rebuild changes the hashes
+    pub fn manifest(&self) -> Option<Arc<ViteManifest>> {
+        let path = self.manifest_path()?;
+        let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+
+        let mut cached = self.manifest.lock().unwrap();
+        if let Some((loaded_at, manifest)) = cached.as_ref() {
+            if *loaded_at == modified {
+                return Some(manifest.clone());
+            }
+        }
+
+        match ViteManifest::load(&path) {
+            Ok(manifest) => {
+                let manifest = Arc::new(manifest);
+                *cached = Some((modified, manifest.clone()));
+                Some(manifest)
+            }
+            Err(e) => {
+                eprintln!("Failed to load Vite manifest {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    pub fn get_html(&self, nonce: &str) -> Result<Vec<u8>, std::io::Error> {
+        let html = String::from_utf8(self.load_asset(HTML_ENTRY)?)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        let html = security::add_nonces(&html, nonce);
+
+        let Some(entry) = self.manifest().and_then(|m| m.entry(HTML_ENTRY)) else {
+            return Ok(html.into_bytes());
+        };
+        Ok(inject_into_head(&html, &entry.to_html(ASSETS_URL, nonce)).into_bytes())
+    }
+}
+
+fn inject_into_head(html: &str, tags: &str) -> String {
+    match html.find("</head>") {
+        Some(index) => format!("{}{}{}", &html[..index], tags, &html[index..]),
+        None => format!("{}{}", tags, html),
+    }
+}
+
+pub fn content_type(path: &str) -> &'static str {
+    match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+        Some("html") => "text/html",
+        Some("css") => "text/css",
+        Some("js") | Some("mjs") => "application/javascript",
+        Some("json") => "application/json",
+        Some("wasm") => "application/wasm",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("woff2") => "font/woff2",
+        Some("mp4") => "video/mp4",
+        Some("webm") => "video/webm",
+        Some("mov") => "video/quicktime",
+        Some("mp3") => "audio/mpeg",
+        Some("m4a") => "audio/mp4",
+        Some("ogg") => "audio/ogg",
+        Some("wav") => "audio/wav",
+        _ => "application/octet-stream",
+    }
+}
@@ -0,0 +1,263 @@
+use std::{
+    collections::HashMap,
+    fs::{self, Metadata},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use flate2::{write::GzEncoder, Compression};
+
+// Compressing tiny files costs more than it saves
+const MIN_COMPRESS_SIZE: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    Brotli,
+    Gzip,
+}
+
+impl Encoding {
+    pub fn header_value(self) -> &'static str {
+        match self {
+            Self::Brotli => "br",
+            Self::Gzip => "gzip",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Brotli => "br",
+            Self::Gzip => "gz",
+        }
+    }
+
+    // The pre-compressed copy a build may have left next to the source
+    fn sibling(self, path: &Path) -> PathBuf {
+        let mut sibling = path.as_os_str().to_owned();
+        sibling.push(".");
+        sibling.push(self.extension());
+        PathBuf::from(sibling)
+    }
+
+    fn compress(self, data: &[u8]) -> Result<Vec<u8>, io::Error> {
+        match self {
+            Self::Brotli => {
+                let mut out = Vec::new();
+                {
+                    let mut writer = brotli::CompressorWriter::new(&mut out, 4096, 9, 22);
+                    writer.write_all(data)?;
+                }
+                Ok(out)
+            }
+            Self::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+        }
+    }
+}
+
+// Encodings the client accepts, in the order we prefer to serve them. A coding named
+// explicitly wins over `*`, so `br;q=0, *` accepts gzip but not br.
+pub fn accepted_encodings(accept_encoding: Option<&str>) -> Vec<Encoding> {
+    let Some(header) = accept_encoding else {
+        return Vec::new();
+    };
+
+    let entries: Vec<(&str, bool)> = header
+        .split(',')
+        .filter_map(|part| {
+            let mut params = part.split(';');
+            let coding = params.next()?.trim();
+            if coding.is_empty() {
+                return None;
+            }
+            let refused = params.any(|param| {
+                let Some((name, value)) = param.split_once('=') else {
+                    return false;
+                };
+                name.trim().eq_ignore_ascii_case("q")
+                    && value.trim().parse::<f32>().is_ok_and(|q| q <= 0.0)
+            });
+            Some((coding, !refused))
+        })
+        .collect();
+    let accepts = |name: &str| {
+        let explicit = entries
+            .iter()
+            .find(|(coding, _)| coding.eq_ignore_ascii_case(name));
+        let wildcard = entries.iter().find(|(coding, _)| *coding == "*");
+        explicit.or(wildcard).is_some_and(|&(_, accepted)| accepted)
+    };
+
+    [Encoding::Brotli, Encoding::Gzip]
+        .into_iter()
+        .filter(|encoding| accepts(encoding.header_value()))
+        .collect()
+}
+
+pub fn is_compressible(content_type: &str) -> bool {
+    content_type.starts_with("text/")
+        || matches!(
+            content_type,
+            "application/javascript" | "application/json" | "application/wasm" | "image/svg+xml"
+        )
+}
+
+// The encoding `CompressionCache::load` serves for a file, decided from metadata alone so
+// validators can be checked before anything is read or compressed
+pub fn negotiate(
+    path: &Path,
+    metadata: &Metadata,
+    content_type: &str,
+    accepted: &[Encoding],
+) -> Option<Encoding> {
+    if let Some(&encoding) = accepted
+        .iter()
+        .find(|encoding| encoding.sibling(path).is_file())
+    {
+        return Some(encoding);
+    }
+    accepted
+        .first()
+        .copied()
+        .filter(|_| is_compressible(content_type) && metadata.len() >= MIN_COMPRESS_SIZE as u64)
+}
+
+pub struct EncodedAsset {
+    pub data: Arc<Vec<u8>>,
+    pub encoding: Option<Encoding>,
+    // Metadata of the uncompressed source, which validators are derived from
+    pub metadata: Metadata,
+}
+
+// Keyed by source path and encoding, tagged with the source mtime it was built from
+type CacheEntries = HashMap<(PathBuf, Encoding), (SystemTime, Arc<Vec<u8>>)>;
+
+// Compressed copies of assets built on demand, invalidated when the source changes
+#[derive(Default)]
+pub struct CompressionCache {
+    entries: Mutex<CacheEntries>,
+}
+
+impl CompressionCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Prefer a pre-compressed sibling from the build, then a cached or fresh compression
+    pub fn load(
+        &self,
+        path: &Path,
+        content_type: &str,
+        accepted: &[Encoding],
+    ) -> Result<EncodedAsset, io::Error> {
+        let metadata = fs::metadata(path)?;
+
+        for &encoding in accepted {
+            if let Ok(data) = fs::read(encoding.sibling(path)) {
+                return Ok(EncodedAsset {
+                    data: Arc::new(data),
+                    encoding: Some(encoding),
+                    metadata,
+                });
+            }
+        }
+
+        let data = fs::read(path)?;
+        let encoding = match accepted.first() {
+            Some(&encoding) if is_compressible(content_type) && data.len() >= MIN_COMPRESS_SIZE => {
+                encoding
+            }
+            _ => {
+                return Ok(EncodedAsset {
+                    data: Arc::new(data),
+                    encoding: None,
+                    metadata,
+                })
+            }
+        };
+
+        let modified = metadata.modified()?;
+        let key = (path.to_path_buf(), encoding);
+        if let Some((cached_at, cached)) = self.entries.lock().unwrap().get(&key) {
+            if *cached_at == modified {
+                return Ok(EncodedAsset {
+                    data: cached.clone(),
+                    encoding: Some(encoding),
+                    metadata,
+                });
+            }
+        }
+
+        let compressed = match encoding.compress(&data) {
+            Ok(compressed) => Arc::new(compressed),
+            Err(e) => {
+                eprintln!("Failed to compress {}: {}", path.display(), e);
+                return Ok(EncodedAsset {
+                    data: Arc::new(data),
+                    encoding: None,
+                    metadata,
+                });
+            }
+        };
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(key, (modified, compressed.clone()));
+
+        Ok(EncodedAsset {
+            data: compressed,
+            encoding: Some(encoding),
+            metadata,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefers_brotli_over_gzip() {
+        assert_eq!(
+            accepted_encodings(Some("gzip, deflate, br")),
+            vec![Encoding::Brotli, Encoding::Gzip]
+        );
+        assert!(accepted_encodings(None).is_empty());
+    }
+
+    #[test]
+    fn explicit_refusal_overrides_wildcard() {
+        assert_eq!(accepted_encodings(Some("br;q=0, *")), vec![Encoding::Gzip]);
+        assert_eq!(
+            accepted_encodings(Some("*, gzip;q=0")),
+            vec![Encoding::Brotli]
+        );
+        assert!(accepted_encodings(Some("*;q=0")).is_empty());
+    }
+
+    #[test]
+    fn identity_refusal_does_not_refuse_others() {
+        assert_eq!(
+            accepted_encodings(Some("gzip, identity;q=0")),
+            vec![Encoding::Gzip]
+        );
+        assert!(accepted_encodings(Some("identity;q=0")).is_empty());
+    }
+
+    #[test]
+    fn ignores_case_and_whitespace() {
+        assert_eq!(
+            accepted_encodings(Some(" BR ; Q = 0 ,  GZip ;q=0.5 ")),
+            vec![Encoding::Gzip]
+        );
+        assert_eq!(
+            accepted_encodings(Some("Br,gzip ; q=1.0")),
+            vec![Encoding::Brotli, Encoding::Gzip]
+        );
+    }
+}
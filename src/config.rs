@@ -0,0 +1,198 @@
+use std::{env, fs, io, path::PathBuf};
+
+use serde::Deserialize;
+use tao::{dpi::LogicalSize, window::WindowBuilder};
+
+// Read from the working directory unless RUST_GUI_CONFIG points elsewhere
+const CONFIG_FILE: &str = "rust-gui.json";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AppConfig {
+    pub window: WindowConfig,
+}
+
+// Everything about how the window looks, applied in one pass by WindowConfig::builder
+// and Platform::style_window
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct WindowConfig {
+    pub title: String,
+    pub width: f64,
+    pub height: f64,
+    pub min_size: Option<WindowSize>,
+    pub max_size: Option<WindowSize>,
+    pub resizable: bool,
+    pub minimizable: bool,
+    pub decorations: bool,
+    pub background_color: Option<Color>,
+    pub corner_radius: f64,
+    pub shadow: bool,
+    // Reopen where the user left the window last time
+    pub remember_state: bool,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct WindowSize {
+    pub width: f64,
+    pub height: f64,
+}
+
+// "#rrggbb" or "#rrggbbaa"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Color {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    pub alpha: u8,
+}
+
+impl TryFrom<String> for Color {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let hex = value.strip_prefix('#').unwrap_or(&value);
+        let rgba = match u32::from_str_radix(hex, 16) {
+            Ok(rgb) if hex.len() == 6 => Some(rgb << 8 | 0xff),
+            Ok(rgba) if hex.len() == 8 => Some(rgba),
+            _ => None,
+        };
+
+        match rgba {
+            Some(rgba) if hex.chars().all(|c| c.is_ascii_hexdigit()) => {
+                let [red, green, blue, alpha] = rgba.to_be_bytes();
+                Ok(Self {
+                    red,
+                    green,
+                    blue,
+                    alpha,
+                })
+            }
+            _ => Err(format!(
+                "Invalid color {:?}, expected #rrggbb or #rrggbbaa",
+                value
+            )),
+        }
+    }
+}
+
+impl Color {
+    pub fn rgba(&self) -> (u8, u8, u8, u8) {
+        (self.red, self.green, self.blue, self.alpha)
+    }
+}
+
+// The look the app has always had: a fixed-size dark window with a title bar and close button
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            title: "React GUI In Rust".to_string(),
+            width: 600.0,
+            height: 300.0,
+            min_size: None,
+            max_size: None,
+            resizable: false,
+            minimizable: false,
+            decorations: true,
+            background_color: Some(Color {
+                red: 0x33,
+                green: 0x33,
+                blue: 0x33,
+                alpha: 0xff,
+            }),
+            corner_radius: 5.0,
+            shadow: true,
+            remember_state: true,
+        }
+    }
+}
+
+impl AppConfig {
+    // A missing file means defaults; a broken one is reported and ignored
+    pub fn load() -> Self {
+        let path = env::var_os("RUST_GUI_CONFIG")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(CONFIG_FILE));
+
+        match fs::read(&path) {
+            Ok(content) => match serde_json::from_slice(&content) {
+                Ok(config) => config,
+                Err(e) => {
+                    eprintln!("Ignoring invalid config {}: {}", path.display(), e);
+                    Self::default()
+                }
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(e) => {
+                eprintln!("Failed to read config {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+}
+
+impl WindowConfig {
+    // The cross-platform part of the configuration; the rest is Platform::style_window
+    pub fn builder(&self) -> WindowBuilder {
+        let mut builder = WindowBuilder::new()
+            .with_title(&self.title)
+            .with_inner_size(LogicalSize::new(self.width, self.height))
+            .with_resizable(self.resizable)
+            .with_minimizable(self.minimizable)
+            .with_maximizable(self.resizable)
+            .with_decorations(self.decorations);
+
+        if let Some(size) = self.min_size {
+            builder = builder.with_min_inner_size(LogicalSize::new(size.width, size.height));
+        }
+        if let Some(size) = self.max_size {
+            builder = builder.with_max_inner_size(LogicalSize::new(size.width, size.height));
+        }
+        if let Some(color) = self.background_color {
+            builder = builder.with_background_color(color.rgba());
+        }
+        builder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn color(value: &str) -> Result<Color, String> {
+        Color::try_from(value.to_string())
+    }
+
+    #[test]
+    fn parses_hex_colors() {
+        assert_eq!(color("#336699").unwrap().rgba(), (0x33, 0x66, 0x99, 0xff));
+        assert_eq!(color("#33669980").unwrap().rgba(), (0x33, 0x66, 0x99, 0x80));
+        assert_eq!(color("AbCdEf").unwrap().rgba(), (0xab, 0xcd, 0xef, 0xff));
+    }
+
+    #[test]
+    fn rejects_other_color_formats() {
+        for value in [
+            "", "#fff", "#12345", "#1234567", "#gg0000", "#+12345", "red",
+        ] {
+            assert!(color(value).is_err(), "{:?} should be rejected", value);
+        }
+    }
+
+    #[test]
+    fn fills_missing_window_fields_with_defaults() {
+        let config: AppConfig = serde_json::from_str(
+            r##"{ "window": { "width": 800, "backgroundColor": "#000000" } }"##,
+        )
+        .unwrap();
+
+        assert_eq!(config.window.width, 800.0);
+        assert_eq!(config.window.height, WindowConfig::default().height);
+        assert_eq!(
+            config.window.background_color.unwrap().rgba(),
+            (0, 0, 0, 0xff)
+        );
+        assert!(config.window.remember_state);
+    }
+}
@@ -0,0 +1,245 @@
+use std::{
+    collections::VecDeque,
+    env,
+    io::{self, BufRead, BufReader, Read},
+    net::{SocketAddr, TcpStream},
+    path::Path,
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::Sender;
+
+pub const DEV_SERVER_PORT: u16 = 5173;
+pub const DEV_SERVER_READY_TIMEOUT: Duration = Duration::from_secs(30);
+
+const DEV_SERVER_HOST: &str = "127.0.0.1";
+const OUTPUT_PREFIX: &str = "[vite]";
+// Number of output lines kept around for the crash page
+const OUTPUT_HISTORY: usize = 20;
+
+// Sent to the event loop when the dev server goes away without being asked to
+#[derive(Debug)]
+pub struct DevServerExit {
+    pub status: Option<ExitStatus>,
+    pub output: Vec<String>,
+}
+
+pub struct DevServer {
+    child: Arc<Mutex<Child>>,
+    port: u16,
+    stopping: Arc<AtomicBool>,
+    output: Arc<Mutex<VecDeque<String>>>,
+}
+
+// The dev server is used for debug builds unless RUST_GUI_DEV_SERVER=0 is set
+pub fn is_enabled() -> bool {
+    match env::var("RUST_GUI_DEV_SERVER") {
+        Ok(value) => value != "0",
+        Err(_) => cfg!(debug_assertions),
+    }
+}
+
+impl DevServer {
+    pub fn spawn(
+        frontend_dir: &Path,
+        port: u16,
+        exit_tx: Sender<DevServerExit>,
+    ) -> Result<Self, io::Error> {
+        // Run the Vite binary directly so killing the child doesn't orphan it behind npm
+        let vite = frontend_dir.join("node_modules").join(".bin").join("vite");
+        if !vite.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "{} not found, run `npm install` in {}",
+                    vite.display(),
+                    frontend_dir.display()
+                ),
+            ));
+        }
+
+        let mut child = Command::new(&vite)
+            .current_dir(frontend_dir)
+            .args(["--host", DEV_SERVER_HOST, "--port", &port.to_string()])
+            .arg("--strictPort")
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let output = Arc::new(Mutex::new(VecDeque::with_capacity(OUTPUT_HISTORY)));
+        if let Some(stdout) = child.stdout.take() {
+            forward_output(stdout, output.clone(), false);
+        }
+        if let Some(stderr) = child.stderr.take() {
+            forward_output(stderr, output.clone(), true);
+        }
+
+        let server = Self {
+            child: Arc::new(Mutex::new(child)),
+            port,
+            stopping: Arc::new(AtomicBool::new(false)),
+            output,
+        };
+        server.supervise(exit_tx);
+
+        Ok(server)
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}:{}", DEV_SERVER_HOST, self.port)
+    }
+
+    // Block until the dev server accepts connections, it exits, or the timeout passes
+    pub fn wait_until_ready(&self, timeout: Duration) -> Result<(), io::Error> {
+        let addr: SocketAddr = format!("{}:{}", DEV_SERVER_HOST, self.port)
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            if TcpStream::connect_timeout(&addr, Duration::from_millis(200)).is_ok() {
+                return Ok(());
+            }
+
+            if let Some(status) = self.child.lock().unwrap().try_wait()? {
+                return Err(io::Error::new(
+                    io::ErrorKind::ConnectionRefused,
+                    format!("Vite dev server exited before it was ready ({})", status),
+                ));
+            }
+
+            if Instant::now() >= deadline {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("Vite dev server not ready after {:?}", timeout),
+                ));
+            }
+
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+
+    pub fn stop(&mut self) {
+        if self.stopping.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let mut child = self.child.lock().unwrap();
+        if let Ok(None) = child.try_wait() {
+            println!("{} Stopping dev server", OUTPUT_PREFIX);
+            if let Err(e) = child.kill() {
+                eprintln!("Failed to stop Vite dev server: {}", e);
+            }
+        }
+        let _ = child.wait();
+    }
+
+    fn supervise(&self, exit_tx: Sender<DevServerExit>) {
+        let child = self.child.clone();
+        let stopping = self.stopping.clone();
+        let output = self.output.clone();
+
+        thread::spawn(move || loop {
+            // Poll rather than wait() so stop() can still take the lock to kill the child
+            let status = child.lock().unwrap().try_wait();
+            if stopping.load(Ordering::SeqCst) {
+                return;
+            }
+
+            let status = match status {
+                Ok(None) => {
+                    thread::sleep(Duration::from_millis(250));
+                    continue;
+                }
+                Ok(Some(status)) => Some(status),
+                Err(e) => {
+                    eprintln!("Failed to check Vite dev server status: {}", e);
+                    None
+                }
+            };
+
+            // Give the output threads a moment to drain the last lines
+            thread::sleep(Duration::from_millis(100));
+            eprintln!(
+                "{} Dev server exited unexpectedly ({:?})",
+                OUTPUT_PREFIX, status
+            );
+            let output = output.lock().unwrap().iter().cloned().collect();
+            let _ = exit_tx.send(DevServerExit { status, output });
+            return;
+        });
+    }
+}
+
+impl Drop for DevServer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn forward_output<R: Read + Send + 'static>(
+    stream: R,
+    history: Arc<Mutex<VecDeque<String>>>,
+    is_stderr: bool,
+) {
+    thread::spawn(move || {
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else { break };
+
+            if is_stderr {
+                eprintln!("{} {}", OUTPUT_PREFIX, line);
+            } else {
+                println!("{} {}", OUTPUT_PREFIX, line);
+            }
+
+            let mut history = history.lock().unwrap();
+            if history.len() == OUTPUT_HISTORY {
+                history.pop_front();
+            }
+            history.push_back(line);
+        }
+    });
+}
+
+pub fn error_page(exit: &DevServerExit) -> String {
+    let status = match exit.status {
+        Some(status) => status.to_string(),
+        None => "unknown status".to_string(),
+    };
+
+    format!(
+        r#"<!doctype html>
+<html>
+<head>
+  <meta charset="UTF-8" />
+  <title>Dev server stopped</title>
+  <style>
+    body {{ margin: 0; padding: 24px; background: #1e1e1e; color: #eee; font: 14px -apple-system, sans-serif; }}
+    h1 {{ font-size: 18px; color: #ff6b6b; }}
+    pre {{ background: #111; padding: 12px; border-radius: 4px; overflow: auto; font: 12px Menlo, monospace; }}
+  </style>
+</head>
+<body>
+  <h1>The Vite dev server stopped</h1>
+  <p>Exited with {}. Restart the app once the problem is fixed.</p>
+  <pre>{}</pre>
+</body>
+</html>"#,
+        escape_html(&status),
+        escape_html(&exit.output.join("\n"))
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
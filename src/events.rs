@@ -0,0 +1,54 @@
+use std::thread;
+
+use serde::Serialize;
+use tao::event_loop::EventLoopProxy;
+
+use crate::{
+    dev_server::DevServerExit, menu::MenuCommand, overlay::ErrorReport,
+    supervisor::SupervisorMessage, watcher::ChangeSet, windows::WindowCommand,
+};
+
+// Everything background threads and callbacks hand to the event loop. Delivering these
+// through an EventLoopProxy wakes the loop, so it can sleep in ControlFlow::Wait otherwise.
+pub enum UserEvent {
+    // IPC responses and anything else Rust runs in a window's page
+    EvaluateScript { window: String, script: String },
+    AssetsChanged(ChangeSet),
+    AssetError(ErrorReport),
+    DevServerExited(DevServerExit),
+    Supervisor(SupervisorMessage),
+    // The window whose page has stashed its state
+    StateStashed(String),
+    Window(WindowCommand),
+    // The id of the clicked menu item
+    MenuClicked(String),
+    Menu(MenuCommand),
+    // Quit from the menu, which guarded windows get a say in
+    QuitRequested,
+    // SIGTERM or SIGINT
+    ShutdownRequested,
+}
+
+// Delivers an event to the page the same way the IPC socket pushes Rust events
+pub fn dispatch_script<T: Serialize>(event: &str, payload: &T) -> String {
+    format!(
+        "window.dispatchEvent(new CustomEvent('rust-event', {{ detail: {{ event: {}, payload: {} }} }}));",
+        serde_json::to_string(event).unwrap_or_default(),
+        serde_json::to_string(payload).unwrap_or_default()
+    )
+}
+
+// Moves every message from a channel onto the event loop until either side goes away
+pub fn forward<I, F>(messages: I, proxy: EventLoopProxy<UserEvent>, wrap: F)
+where
+    I: IntoIterator + Send + 'static,
+    F: Fn(I::Item) -> UserEvent + Send + 'static,
+{
+    thread::spawn(move || {
+        for message in messages {
+            if proxy.send_event(wrap(message)).is_err() {
+                break;
+            }
+        }
+    });
+}
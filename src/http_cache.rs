@@ -0,0 +1,241 @@
+use std::{
+    fs::Metadata,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use wry::http::{header, HeaderMap};
+
+use crate::compression::Encoding;
+
+// Vite's content hashes are 8 characters from the url-safe base64 alphabet
+const VITE_HASH_LEN: usize = 8;
+
+pub const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+pub const REVALIDATE: &str = "no-cache";
+
+pub struct Validators {
+    pub etag: String,
+    pub last_modified: Option<SystemTime>,
+}
+
+impl Validators {
+    // Strong validators, distinct per encoding so each representation caches separately
+    pub fn from_metadata(metadata: &Metadata, encoding: Option<Encoding>) -> Self {
+        let last_modified = metadata.modified().ok();
+        let mtime = last_modified
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .unwrap_or_default();
+
+        let mut etag = format!("\"{:x}-{:x}", metadata.len(), mtime.as_nanos());
+        if let Some(encoding) = encoding {
+            etag.push('-');
+            etag.push_str(encoding.header_value());
+        }
+        etag.push('"');
+
+        Self {
+            etag,
+            // HTTP dates only carry whole seconds
+            last_modified: last_modified.map(truncate_to_seconds),
+        }
+    }
+
+    // Distinguishes representations that differ for reasons the file metadata can't see
+    pub fn varied_by(mut self, variant: &str) -> Self {
+        self.etag
+            .insert_str(self.etag.len() - 1, &format!("-{}", variant));
+        self
+    }
+
+    // Another file the representation is built from; a newer one also moves Last-Modified
+    pub fn varied_by_file(self, modified: SystemTime) -> Self {
+        let nanos = modified
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let modified = truncate_to_seconds(modified);
+        let mut validators = self.varied_by(&format!("{:x}", nanos));
+        validators.last_modified = validators
+            .last_modified
+            .map(|last_modified| last_modified.max(modified));
+        validators
+    }
+
+    pub fn last_modified_header(&self) -> Option<String> {
+        self.last_modified.map(httpdate::fmt_http_date)
+    }
+
+    // If-None-Match wins over If-Modified-Since when both are present (RFC 9110 13.2.2)
+    pub fn is_not_modified(&self, headers: &HeaderMap) -> bool {
+        if let Some(if_none_match) = header_str(headers, header::IF_NONE_MATCH) {
+            return if_none_match.split(',').any(|tag| {
+                let tag = tag.trim();
+                tag == "*" || weak_eq(tag, &self.etag)
+            });
+        }
+
+        match (
+            header_str(headers, header::IF_MODIFIED_SINCE)
+                .and_then(|date| httpdate::parse_http_date(date).ok()),
+            self.last_modified,
+        ) {
+            (Some(since), Some(modified)) => modified <= since,
+            _ => false,
+        }
+    }
+}
+
+// If-Range only honours strong validators: the exact ETag or the exact Last-Modified date
+pub fn if_range_matches(validators: &Validators, headers: &HeaderMap) -> bool {
+    match header_str(headers, header::IF_RANGE) {
+        None => true,
+        Some(value) if value.starts_with("W/") => false,
+        Some(value) if value.starts_with('"') => value == validators.etag,
+        Some(value) => match (httpdate::parse_http_date(value), validators.last_modified) {
+            (Ok(date), Some(modified)) => date == modified,
+            _ => false,
+        },
+    }
+}
+
+// Hashed filenames from Vite never change content, everything else must be revalidated
+pub fn cache_control(path: &str) -> &'static str {
+    if is_content_hashed(path) {
+        IMMUTABLE
+    } else {
+        REVALIDATE
+    }
+}
+
+fn is_content_hashed(path: &str) -> bool {
+    let Some(stem) = Path::new(path).file_stem().and_then(|stem| stem.to_str()) else {
+        return false;
+    };
+    // The hash alphabet includes '-', so take a fixed-width suffix rather than splitting
+    let Some(split) = stem.len().checked_sub(VITE_HASH_LEN + 1) else {
+        return false;
+    };
+    if split == 0 || !stem.is_char_boundary(split) || !stem[split..].starts_with('-') {
+        return false;
+    }
+    let hash = &stem[split + 1..];
+
+    hash.chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        // Plain words like "settings" are names, not hashes
+        && hash
+            .chars()
+            .any(|c| c.is_ascii_digit() || c.is_ascii_uppercase() || c == '_' || c == '-')
+}
+
+pub fn header_str(headers: &HeaderMap, name: header::HeaderName) -> Option<&str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+fn weak_eq(a: &str, b: &str) -> bool {
+    a.trim_start_matches("W/") == b.trim_start_matches("W/")
+}
+
+fn truncate_to_seconds(time: SystemTime) -> SystemTime {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(since_epoch) => UNIX_EPOCH + Duration::from_secs(since_epoch.as_secs()),
+        Err(_) => time,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use wry::http::HeaderValue;
+
+    fn validators() -> Validators {
+        Validators {
+            etag: "\"1f-abc\"".to_string(),
+            last_modified: Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000)),
+        }
+    }
+
+    fn headers(pairs: &[(header::HeaderName, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(name.clone(), HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    fn http_date(secs: u64) -> String {
+        httpdate::fmt_http_date(UNIX_EPOCH + Duration::from_secs(secs))
+    }
+
+    #[test]
+    fn matches_if_none_match() {
+        let validators = validators();
+        for value in ["\"1f-abc\"", "W/\"1f-abc\"", "\"other\", \"1f-abc\"", "*"] {
+            assert!(validators.is_not_modified(&headers(&[(header::IF_NONE_MATCH, value)])));
+        }
+        assert!(!validators.is_not_modified(&headers(&[(header::IF_NONE_MATCH, "\"other\"")])));
+    }
+
+    #[test]
+    fn if_none_match_wins_over_if_modified_since() {
+        let validators = validators();
+        let since = http_date(1_800_000_000);
+        assert!(validators.is_not_modified(&headers(&[(header::IF_MODIFIED_SINCE, &since)])));
+        assert!(!validators.is_not_modified(&headers(&[
+            (header::IF_NONE_MATCH, "\"other\""),
+            (header::IF_MODIFIED_SINCE, &since),
+        ])));
+        let before = http_date(1_600_000_000);
+        assert!(!validators.is_not_modified(&headers(&[(header::IF_MODIFIED_SINCE, &before)])));
+    }
+
+    #[test]
+    fn if_range_needs_a_strong_match() {
+        let validators = validators();
+        assert!(if_range_matches(&validators, &HeaderMap::new()));
+        assert!(if_range_matches(
+            &validators,
+            &headers(&[(header::IF_RANGE, "\"1f-abc\"")])
+        ));
+        assert!(!if_range_matches(
+            &validators,
+            &headers(&[(header::IF_RANGE, "W/\"1f-abc\"")])
+        ));
+        assert!(if_range_matches(
+            &validators,
+            &headers(&[(header::IF_RANGE, &http_date(1_700_000_000))])
+        ));
+        assert!(!if_range_matches(
+            &validators,
+            &headers(&[(header::IF_RANGE, &http_date(1_700_000_001))])
+        ));
+    }
+
+    #[test]
+    fn variants_change_the_etag() {
+        assert_eq!(validators().varied_by("nonce").etag, "\"1f-abc-nonce\"");
+
+        let later = UNIX_EPOCH + Duration::from_secs(1_750_000_000);
+        let varied = validators().varied_by_file(later);
+        assert_ne!(varied.etag, validators().etag);
+        assert_eq!(varied.last_modified, Some(later));
+    }
+
+    #[test]
+    fn recognizes_vite_content_hashes() {
+        assert!(is_content_hashed("assets/index-BdOw3a7x.js"));
+        assert!(is_content_hashed("assets/logo-a1b2c3d4.png"));
+        assert!(is_content_hashed("assets/vendor-D_-x9Yz1.css"));
+        assert_eq!(cache_control("assets/index-BdOw3a7x.js"), IMMUTABLE);
+
+        assert!(!is_content_hashed("index.html"));
+        assert!(!is_content_hashed("vite.svg"));
+        assert!(!is_content_hashed("assets/settings-page.js"));
+        // Eight lowercase letters are a word, not a hash
+        assert!(!is_content_hashed("assets/index-settings.js"));
+        assert!(!is_content_hashed("-BdOw3a7x.js"));
+        assert_eq!(cache_control("index.html"), REVALIDATE);
+    }
+}
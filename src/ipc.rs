@@ -0,0 +1,118 @@
+use std::{
+    error::Error,
+    fmt,
+    sync::{mpsc, Arc},
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+// Structured message types
+#[derive(Debug, Deserialize)]
+pub struct IpcRequest {
+    pub function: String,
+    pub args: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IpcResponse {
+    pub success: bool,
+    pub data: Option<String>,
+    pub error: Option<String>,
+}
+
+// Error handling
+#[derive(Debug)]
+pub enum AppError {
+    InvalidArgCount {
+        function: String,
+        expected: usize,
+        got: usize,
+    },
+    ParseError {
+        message: String,
+    },
+    UnknownFunction(String),
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidArgCount {
+                function,
+                expected,
+                got,
+            } => write!(
+                f,
+                "Invalid argument count for {}: expected {}, got {}",
+                function, expected, got
+            ),
+            Self::ParseError { message } => write!(f, "Parse error: {}", message),
+            Self::UnknownFunction(name) => write!(f, "Unknown function: {}", name),
+        }
+    }
+}
+
+impl Error for AppError {}
+
+// Protocol handlers
+pub trait ProtocolHandler: Send + Sync {
+    fn handle(&self, function: &str, args: &[String]) -> Result<String, AppError>;
+}
+
+// Runs one serialized IpcRequest through the handler, whatever transport it came in on
+pub fn dispatch(body: &str, protocol_handler: &dyn ProtocolHandler) -> IpcResponse {
+    match serde_json::from_str::<IpcRequest>(body) {
+        Ok(req) => match protocol_handler.handle(&req.function, &req.args) {
+            Ok(result) => IpcResponse {
+                success: true,
+                data: Some(result),
+                error: None,
+            },
+            Err(e) => IpcResponse {
+                success: false,
+                data: None,
+                error: Some(e.to_string()),
+            },
+        },
+        Err(e) => IpcResponse {
+            success: false,
+            data: None,
+            error: Some(format!("Failed to parse message: {}", e)),
+        },
+    }
+}
+
+// Picks the commands of one of the client scripts out of the IPC traffic. They are JSON in
+// the first argument of calls to that script's function.
+pub fn command<T: DeserializeOwned>(body: &str, function: &str) -> Option<T> {
+    let request = serde_json::from_str::<IpcRequest>(body).ok()?;
+    if request.function != function {
+        return None;
+    }
+
+    let command = request.args.first()?;
+    match serde_json::from_str(command) {
+        Ok(command) => Some(command),
+        Err(e) => {
+            eprintln!("Invalid {} command {}: {}", function, command, e);
+            None
+        }
+    }
+}
+
+pub fn handle_ipc_message(
+    body: &str,
+    tx: Arc<mpsc::Sender<String>>,
+    protocol_handler: Arc<dyn ProtocolHandler>,
+) {
+    let response = dispatch(body, protocol_handler.as_ref());
+
+    let js = format!(
+        "window.dispatchEvent(new CustomEvent('rust-response', {{ detail: {} }}));",
+        serde_json::to_string(&response).unwrap_or_default()
+    );
+
+    if let Err(e) = tx.send(js) {
+        eprintln!("Failed to send response: {}", e);
+    }
+}
@@ -1,136 +1,53 @@
-use cocoa::{
-    appkit::{NSApplication, NSApplicationActivationPolicy::NSApplicationActivationPolicyRegular},
-    base::nil,
-};
-use std::{
-    sync::mpsc::{channel as std_channel, TryRecvError},
-    time::Instant,
-};
-
-use notify::{Event as NotifyEvent, RecursiveMode, Watcher};
-
-use objc::{class, msg_send, sel, sel_impl};
-use serde::{Deserialize, Serialize};
-use std::{
-    env,
-    error::Error,
-    fmt, fs,
-    path::{Path, PathBuf},
-    sync::{mpsc, Arc},
-    time::Duration,
-};
+use std::{collections::HashMap, mem, path::Path, sync::Arc, time::Instant};
 use tao::{
     event::{Event, StartCause, WindowEvent},
-    event_loop::{ControlFlow, EventLoop},
-    window::{Icon, WindowBuilder},
+    event_loop::{ControlFlow, EventLoopBuilder},
 };
-use wry::{Result as WryResult, WebViewBuilder};
-
-mod gui;
-
-const WINDOW_WIDTH: f64 = 600.0;
-const WINDOW_HEIGHT: f64 = 300.0;
-const APP_HTML: &[u8] = include_bytes!("../frontend/dist/index.html");
-const APP_BUNDLE: &[u8] = include_bytes!("../frontend/dist/assets/index.js");
-const APP_CSS: &[u8] = include_bytes!("../frontend/dist/assets/style.css");
-
-struct AssetManager {
-    base_path: PathBuf,
-}
-
-impl AssetManager {
-    fn new() -> Result<Self, ()> {
-        // Get the executable's directory
-        let exe_dir = env::current_exe()
-            .map_err(|_| ())?
-            .parent()
-            .ok_or(())?
-            .to_path_buf();
-
-        // In development, use the frontend/dist directory
-        let dev_path = Path::new("frontend/dist");
-
-        // Check if we're running in development or production
-        let base_path = if dev_path.exists() {
-            dev_path.to_path_buf()
-        } else {
-            // In production, look for assets in a directory next to the executable
-            exe_dir.join("assets")
-        };
-
-        Ok(Self { base_path })
-    }
-
-    fn load_asset(&self, relative_path: &str) -> Result<Vec<u8>, std::io::Error> {
-        let path = self.base_path.join(relative_path);
-        fs::read(&path)
-    }
-
-    fn get_html(&self) -> Result<Vec<u8>, std::io::Error> {
-        self.load_asset("index.html")
-    }
-
-    fn get_js(&self) -> Result<Vec<u8>, std::io::Error> {
-        self.load_asset("assets/index.js")
-    }
-
-    fn get_css(&self) -> Result<Vec<u8>, std::io::Error> {
-        self.load_asset("assets/style.css")
-    }
-}
-
-// Structured message types
-#[derive(Debug, Deserialize)]
-struct IpcRequest {
-    function: String,
-    args: Vec<String>,
-}
-
-#[derive(Debug, Serialize)]
-struct IpcResponse {
-    success: bool,
-    data: Option<String>,
-    error: Option<String>,
-}
-
-// Error handling
-#[derive(Debug)]
-enum AppError {
-    InvalidArgCount {
-        function: String,
-        expected: usize,
-        got: usize,
-    },
-    ParseError {
-        message: String,
-    },
-    UnknownFunction(String),
-}
-
-impl fmt::Display for AppError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::InvalidArgCount {
-                function,
-                expected,
-                got,
-            } => write!(
-                f,
-                "Invalid argument count for {}: expected {}, got {}",
-                function, expected, got
-            ),
-            Self::ParseError { message } => write!(f, "Parse error: {}", message),
-            Self::UnknownFunction(name) => write!(f, "Unknown function: {}", name),
-        }
-    }
-}
-
-impl Error for AppError {}
-
-// Protocol handlers
-trait ProtocolHandler: Send + Sync {
-    fn handle(&self, function: &str, args: &[String]) -> Result<String, AppError>;
-}
+use wry::Result as WryResult;
+
+mod assets;
+mod compression;
+mod config;
+mod dev_server;
+mod events;
+mod http_cache;
+mod ipc;
+mod manifest;
+mod menu;
+mod overlay;
+mod platform;
+mod protocol;
+mod range;
+mod schemes;
+mod security;
+mod server;
+mod shortcuts;
+mod shutdown;
+mod state_stash;
+mod supervisor;
+mod watcher;
+mod window_state;
+mod windows;
+
+use assets::AssetManager;
+use config::AppConfig;
+use dev_server::{DevServer, DEV_SERVER_PORT, DEV_SERVER_READY_TIMEOUT};
+use events::UserEvent;
+use ipc::{AppError, ProtocolHandler};
+use menu::{AppMenu, MenuCommand, MenuError};
+use muda::MenuEvent;
+use overlay::ErrorReport;
+use platform::Platform;
+use schemes::SchemeRegistry;
+use security::SecurityPolicy;
+use server::IpcServer;
+use shortcuts::{ShortcutAction, ShortcutRegistry};
+use shutdown::ShutdownHooks;
+use supervisor::SupervisorMessage;
+use watcher::{AssetWatcher, ChangeKind, WatchConfig};
+use windows::{WebViewSettings, WindowCommand, WindowError, WindowManager, MAIN_WINDOW};
+
+const APP_URL: &str = "application://index.html";
 
 struct TuffiProtocolHandler;
 
@@ -167,13 +84,76 @@ impl TuffiProtocolHandler {
     }
 }
 
-// Modified WebView setup function
-fn setup_webview(
-    window: &tao::window::Window,
-    protocol_handler: Arc<dyn ProtocolHandler>,
-) -> WryResult<(wry::WebView, mpsc::Receiver<String>)> {
-    let (tx, rx) = mpsc::channel();
-    let tx = Arc::new(tx);
+// JS changes re-run the page, HTML changes need the document itself fetched again
+fn reload_page(webview: &wry::WebView, kind: ChangeKind) -> WryResult<()> {
+    match kind {
+        ChangeKind::Document => webview.url().and_then(|url| webview.load_url(&url)),
+        _ => webview.evaluate_script("location.reload()"),
+    }
+}
+
+fn main() -> WryResult<()> {
+    let protocol_handler = Arc::new(TuffiProtocolHandler);
+
+    // Dev supervisor: no window of its own, it rebuilds and restarts the app on changes
+    if supervisor::is_requested() {
+        if let Err(e) = supervisor::run() {
+            eprintln!("Supervisor failed: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Browser mode: no window, just the frontend and IPC over HTTP
+    if let Some(options) = server::serve_mode() {
+        if let Err(e) = server::run(options, protocol_handler) {
+            eprintln!("Failed to run server: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Start Vite first so it boots while the window is being created
+    let (dev_server_tx, dev_server_rx) = crossbeam_channel::unbounded();
+    let mut dev_server = if dev_server::is_enabled() {
+        match DevServer::spawn(Path::new("frontend"), DEV_SERVER_PORT, dev_server_tx) {
+            Ok(server) => Some(server),
+            Err(e) => {
+                eprintln!("Failed to start Vite dev server: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let config = AppConfig::load();
+    let event_loop = EventLoopBuilder::<UserEvent>::with_user_event().build();
+    let proxy = event_loop.create_proxy();
+    events::forward(dev_server_rx, proxy.clone(), UserEvent::DevServerExited);
+
+    let platform = platform::native();
+    platform.activate();
+    platform.set_app_name(&config.window.title);
+    if let Some(supervisor_rx) = supervisor::messages() {
+        events::forward(supervisor_rx, proxy.clone(), UserEvent::Supervisor);
+    }
+    match shutdown::signals() {
+        Ok(signals_rx) => {
+            events::forward(signals_rx, proxy.clone(), |_| UserEvent::ShutdownRequested)
+        }
+        Err(e) => eprintln!("Failed to handle shutdown signals: {}", e),
+    }
+
+    let start_url = match &dev_server {
+        Some(server) => {
+            if let Err(e) = server.wait_until_ready(DEV_SERVER_READY_TIMEOUT) {
+                eprintln!("Vite dev server is not responding: {}", e);
+            }
+            server.url()
+        }
+        None => APP_URL.to_string(),
+    };
 
     // Create asset manager
     let asset_manager = match AssetManager::new() {
@@ -184,216 +164,333 @@ fn setup_webview(
         }
     };
 
-    let webview = WebViewBuilder::new()
-        .with_initialization_script(&format!(
-            "
-            document.addEventListener('DOMContentLoaded', () => {{
-                const style = document.createElement('style');
-                style.textContent = `{}`;
-                document.head.appendChild(style);
-            }});
-        ",
-            r#"
-                * {
-                    cursor: default !important;
-                    -webkit-user-select: none;
-                    -moz-user-select: none;
-                    -ms-user-select: none;
-                    user-select: none;
-                }
-            "#
-        ))
-        .with_url("application://index.html")
-        .with_ipc_handler(move |req| {
-            let tx = tx.clone();
-            let handler = protocol_handler.clone();
-            handle_ipc_message(req.body(), tx, handler);
-        })
-        .with_initialization_script(
-            r#"
-            // Enable HMR support detection
-            window.__HMR_ENABLED__ = true;
-        "#,
-        )
-        .with_url("http://localhost:5173")
-        .with_custom_protocol("application".into(), {
-            let asset_manager = asset_manager.clone();
-            move |_req, _resp| match asset_manager.get_html() {
-                Ok(content) => wry::http::Response::builder()
-                    .header("Content-Type", "text/html")
-                    .body(std::borrow::Cow::Owned(content))
-                    .unwrap(),
-                Err(e) => {
-                    eprintln!("Failed to load HTML: {}", e);
-                    wry::http::Response::builder()
-                        .status(500)
-                        .body(std::borrow::Cow::Owned(Vec::new()))
-                        .unwrap()
-                }
-            }
-        })
-        .with_custom_protocol("assets".into(), {
-            let asset_manager = asset_manager.clone();
-            move |_, req| {
-                let path = req.uri().path();
-
-                let (content_type, content) = if path.ends_with(".css") {
-                    ("text/css", asset_manager.get_css())
-                } else if path.ends_with(".js") {
-                    ("application/javascript", asset_manager.get_js())
-                } else if path.ends_with(".wasm") {
-                    ("application/wasm", asset_manager.get_js())
-                } else {
-                    ("application/octet-stream", asset_manager.get_js())
-                };
+    // Stylesheets are swapped by their URL under this directory
+    let asset_root = asset_manager.base_path().to_path_buf();
 
-                match content {
-                    Ok(data) => wry::http::Response::builder()
-                        .header("Content-Type", content_type)
-                        .header("Access-Control-Allow-Origin", "*")
-                        .header("Access-Control-Allow-Methods", "GET, POST, OPTIONS")
-                        .header("Access-Control-Allow-Headers", "Content-Type")
-                        .header("Cross-Origin-Opener-Policy", "same-origin")
-                        .header("Cross-Origin-Embedder-Policy", "require-corp")
-                        .body(std::borrow::Cow::Owned(data))
-                        .unwrap(),
-                    Err(e) => {
-                        eprintln!("Failed to load asset {}: {}", path, e);
-                        wry::http::Response::builder()
-                            .status(404)
-                            .body(std::borrow::Cow::Owned(Vec::new()))
-                            .unwrap()
-                    }
-                }
-            }
+    let mut security = SecurityPolicy::for_build();
+    // Built-in schemes go through the same registry as user schemes
+    let mut schemes = SchemeRegistry::new();
+    schemes
+        .register("application", {
+            let nonce = security.nonce().to_string();
+            move |req| Ok(protocol::serve_application(&asset_manager, &nonce, req))
         })
-        .build(window)?;
+        .and_then(|schemes| schemes.register("assets", schemes::directory(&asset_root)))
+        .expect("Built-in schemes are only registered once");
+    // The CSP lets pages load from every registered scheme
+    security.allow_schemes(schemes.names());
+    let security = Arc::new(security);
+
+    // Development builds show failed asset requests in the error overlay
+    let (errors_tx, errors_rx) = crossbeam_channel::unbounded();
+    if cfg!(debug_assertions) {
+        schemes.report_errors(errors_tx);
+    }
+    events::forward(errors_rx, proxy.clone(), UserEvent::AssetError);
 
-    Ok((webview, rx))
-}
+    // Frontend stores survive hot reloads in each window's state stash
+    let mut pending_reloads: HashMap<String, (ChangeKind, Instant)> = HashMap::new();
 
-fn handle_ipc_message(
-    body: &str,
-    tx: Arc<mpsc::Sender<String>>,
-    protocol_handler: Arc<dyn ProtocolHandler>,
-) {
-    let response = match serde_json::from_str::<IpcRequest>(body) {
-        Ok(req) => match protocol_handler.handle(&req.function, &req.args) {
-            Ok(result) => IpcResponse {
-                success: true,
-                data: Some(result),
-                error: None,
-            },
-            Err(e) => IpcResponse {
-                success: false,
-                data: None,
-                error: Some(e.to_string()),
-            },
-        },
-        Err(e) => IpcResponse {
-            success: false,
-            data: None,
-            error: Some(format!("Failed to parse message: {}", e)),
-        },
+    // WebSocket IPC for high-volume traffic; window.ipc keeps working without it
+    let mut ipc_server = match IpcServer::start(protocol_handler.clone()) {
+        Ok(ipc_server) => Some(ipc_server),
+        Err(e) => {
+            eprintln!("Failed to start IPC WebSocket server: {}", e);
+            None
+        }
     };
+    let emitter = ipc_server.as_ref().map(IpcServer::emitter);
 
-    let js = format!(
-        "window.dispatchEvent(new CustomEvent('rust-response', {{ detail: {} }}));",
-        serde_json::to_string(&response).unwrap_or_default()
-    );
-
-    if let Err(e) = tx.send(js) {
-        eprintln!("Failed to send response: {}", e);
+    // Run before the app exits, while IPC and the windows are still up
+    let mut shutdown_hooks = ShutdownHooks::new();
+    if let Some(emitter) = emitter.clone() {
+        shutdown_hooks.register("notify-frontend", move || emitter.emit("shutdown", &()));
     }
-}
 
-fn main() -> WryResult<()> {
-    let event_loop = EventLoop::new();
-    let window = WindowBuilder::new()
-        .with_inner_size(tao::dpi::LogicalSize::new(WINDOW_WIDTH, WINDOW_HEIGHT))
-        .build(&event_loop)
-        .expect("Failed to build window");
-
-    unsafe {
-        let app = NSApplication::sharedApplication(nil);
-        let _: () = msg_send![app, setActivationPolicy: NSApplicationActivationPolicyRegular];
-
-        app.activateIgnoringOtherApps_(true);
-        gui::make_borderless(&window);
-        gui::disable_window_resize(&window);
-        gui::show_titlebar_and_controls(&window);
-        gui::create_menu_bar("React GUI In Rust");
+    let ipc_client_script = ipc_server
+        .as_ref()
+        .map(|server| server.client_script(&start_url));
+    let settings = WebViewSettings {
+        start_url,
+        security,
+        schemes,
+        protocol_handler,
+        ipc_client_script,
+    };
+    let mut windows = WindowManager::new(platform, settings, proxy.clone());
+    match windows.open(&event_loop, MAIN_WINDOW, &config.window, None) {
+        Ok(_) => (),
+        Err(WindowError::WebView(e)) => return Err(e),
+        Err(e) => panic!("{}", e),
     }
 
-    let protocol_handler = Arc::new(TuffiProtocolHandler);
-    let (webview, rx) = setup_webview(&window, protocol_handler)?;
-    let webview = Arc::new(webview);
-
-    // Initialize webview with HMR support script
-
-    // Set up file watcher with crossbeam channel for better performance
-    let (watcher_tx, watcher_rx) = crossbeam_channel::unbounded();
-    let mut watcher =
-        notify::recommended_watcher(move |res: Result<NotifyEvent, notify::Error>| {
-            if let Ok(event) = res {
-                if event.kind.is_modify() || event.kind.is_create() || event.kind.is_remove() {
-                    // Check if any of the modified paths have .js or .css extension
-                    let should_reload = event.paths.iter().any(|path| {
-                        if let Some(ext) = path.extension() {
-                            matches!(ext.to_str(), Some("js") | Some("css") | Some("html"))
-                        } else {
-                            false
-                        }
-                    });
-
-                    if should_reload {
-                        println!("Detected change in JS/CSS file: {:?}", event.paths);
-                        let _ = watcher_tx.try_send(());
-                    }
-                }
-            }
+    // App-level shortcuts work whichever window, menu or dialog has focus
+    let mut shortcuts = ShortcutRegistry::new();
+    let focus_main = {
+        let proxy = proxy.clone();
+        move || {
+            let _ = proxy.send_event(UserEvent::Window(WindowCommand::Focus {
+                label: MAIN_WINDOW.to_string(),
+            }));
+        }
+    };
+    if let Err(e) = shortcuts
+        .register(
+            "command-palette",
+            "Command Palette",
+            "CmdOrCtrl+K",
+            ShortcutAction::Emit,
+        )
+        .and_then(|shortcuts| {
+            shortcuts.register(
+                "focus-main",
+                "Main Window",
+                "CmdOrCtrl+1",
+                ShortcutAction::Run(Box::new(focus_main)),
+            )
         })
-        .expect("Failed to create file watcher");
+    {
+        eprintln!("Failed to register shortcut: {}", e);
+    }
 
-    // Watch the assets directory
-    watcher
-        .watch(Path::new("frontend/dist"), RecursiveMode::Recursive)
-        .expect("Failed to watch assets directory");
+    // Rust handlers see menu clicks first, then every page gets them as a rust-event
+    let mut app_menu = AppMenu::build(
+        &menu::default_menu(&config.window.title),
+        shortcuts,
+        &config.window.title,
+    )
+    .expect("The default menu is valid");
+    app_menu.on("reload", {
+        let proxy = proxy.clone();
+        move |_| {
+            let _ = proxy.send_event(UserEvent::EvaluateScript {
+                window: MAIN_WINDOW.to_string(),
+                script: "location.reload()".to_string(),
+            });
+        }
+    });
+    app_menu.on(menu::QUIT_ID, {
+        let proxy = proxy.clone();
+        move |_| {
+            let _ = proxy.send_event(UserEvent::QuitRequested);
+        }
+    });
+    if let Err(e) = windows.set_menu(app_menu.menu()) {
+        eprintln!("Failed to set menu: {}", e);
+    }
+    events::forward(MenuEvent::receiver().clone(), proxy.clone(), |event| {
+        UserEvent::MenuClicked(event.id.0)
+    });
 
-    event_loop.run(move |event, _, control_flow| {
-        // Use Poll mode for more responsive events
-        *control_flow = ControlFlow::Poll;
+    // Bursts of file changes are coalesced into a single reload
+    let (watcher_tx, watcher_rx) = crossbeam_channel::unbounded();
+    let mut watcher = match AssetWatcher::spawn(&WatchConfig::from_env(), watcher_tx) {
+        Ok(watcher) => Some(watcher),
+        Err(e) => {
+            eprintln!("Hot reload disabled: {}", e);
+            None
+        }
+    };
+    events::forward(watcher_rx, proxy, UserEvent::AssetsChanged);
 
+    let mut shutdown_requested = false;
+    event_loop.run(move |event, target, control_flow| {
         match event {
             Event::NewEvents(StartCause::Init) => (),
             Event::WindowEvent {
+                window_id,
                 event: WindowEvent::CloseRequested,
                 ..
             } => {
-                *control_flow = ControlFlow::Exit;
+                if let Some(label) = windows.label(window_id).map(str::to_string) {
+                    if windows.request_close(&label) {
+                        windows.approve_close(&label);
+                    }
+                }
+            }
+            Event::WindowEvent {
+                window_id, event, ..
+            } => {
+                if let WindowEvent::Moved(_) | WindowEvent::Resized(_) = event {
+                    windows.track(window_id);
+                }
+                windows.notify(window_id, &event);
+            }
+            Event::UserEvent(UserEvent::EvaluateScript { window, script }) => {
+                // The window may have closed while the reply was on its way
+                if let Some(managed) = windows.get(&window) {
+                    if let Err(e) = managed.webview.evaluate_script(&script) {
+                        eprintln!("Failed to evaluate script: {}", e);
+                    }
+                    managed.window.request_redraw();
+                }
+            }
+            Event::UserEvent(UserEvent::Window(command)) => windows.handle(target, command),
+            Event::UserEvent(UserEvent::MenuClicked(id)) => {
+                if let Some(script) = app_menu.click(&id) {
+                    if let Err(e) = windows.evaluate_all(&script) {
+                        eprintln!("Failed to deliver menu click: {}", e);
+                    }
+                }
             }
-            Event::MainEventsCleared => {
-                // Use try_recv in a loop to handle all pending events
-                while let Ok(()) = watcher_rx.try_recv() {
-                    println!(
-                        "File change detected at {:?}, reloading",
-                        std::time::SystemTime::now()
-                    );
-                    if let Err(e) = webview.evaluate_script("import.meta.hot.invalidate()") {
-                        eprintln!("Failed to reload page: {}", e);
+            Event::UserEvent(UserEvent::Menu(command)) => {
+                let result = match command {
+                    MenuCommand::Set { items } => app_menu
+                        .replace(&items)
+                        .and_then(|()| windows.set_menu(app_menu.menu()).map_err(MenuError::from)),
+                    MenuCommand::SetEnabled { id, enabled } => app_menu.set_enabled(&id, enabled),
+                    MenuCommand::SetChecked { id, checked } => app_menu.set_checked(&id, checked),
+                };
+                if let Err(e) = result {
+                    eprintln!("Failed to update menu: {}", e);
+                }
+            }
+            Event::UserEvent(UserEvent::Supervisor(message)) => {
+                let result = match message {
+                    SupervisorMessage::BuildStarted => {
+                        println!("Rebuilding Rust backend");
+                        windows.evaluate_all(&overlay::hide_script())
+                    }
+                    // This process stays up with the last good build until cargo succeeds
+                    SupervisorMessage::BuildFailed { output } => windows.evaluate_all(
+                        &overlay::show_script(&ErrorReport::new("Rust build", output)),
+                    ),
+                    SupervisorMessage::Shutdown => {
+                        shutdown_requested = true;
+                        Ok(())
+                    }
+                };
+                if let Err(e) = result {
+                    eprintln!("Failed to update build status: {}", e);
+                }
+            }
+            Event::UserEvent(UserEvent::QuitRequested) => {
+                shutdown_requested |= windows.request_quit()
+            }
+            Event::UserEvent(UserEvent::ShutdownRequested) => shutdown_requested = true,
+            // The app was quit from outside the loop, e.g. a menu with the predefined Quit role
+            // on macOS, which terminates the app without closing its windows first
+            Event::LoopDestroyed => shutdown_requested = true,
+            // Replace the blank views with an error page if Vite died under us
+            Event::UserEvent(UserEvent::DevServerExited(exit)) => {
+                let page = dev_server::error_page(&exit);
+                for (_, managed) in windows.iter() {
+                    if let Err(e) = managed.webview.load_html(&page) {
+                        eprintln!("Failed to show dev server error page: {}", e);
+                    }
+                }
+                dev_server = None;
+            }
+            Event::UserEvent(UserEvent::AssetError(report)) => {
+                if let Err(e) = windows.evaluate_all(&overlay::show_script(&report)) {
+                    eprintln!("Failed to show error overlay: {}", e);
+                }
+            }
+            Event::UserEvent(UserEvent::AssetsChanged(mut changes)) => {
+                // A failed Vite build leaves an error report, the next build removes it
+                if let Some(path) = changes.files.remove(overlay::BUILD_ERROR_FILE) {
+                    let script = match ErrorReport::load(&path) {
+                        Ok(report) => {
+                            println!("Vite build failed: {}", report.message);
+                            overlay::show_script(&report)
+                        }
+                        Err(_) => overlay::hide_script(),
+                    };
+                    if let Err(e) = windows.evaluate_all(&script) {
+                        eprintln!("Failed to update error overlay: {}", e);
                     }
-                    window.request_redraw();
                 }
 
-                // Handle other events
-                while let Ok(js) = rx.try_recv() {
-                    if let Err(e) = webview.evaluate_script(&js) {
-                        eprintln!("Failed to evaluate script: {}", e);
+                if let Some(emitter) = &emitter {
+                    emitter.emit("assets-changed", &changes.files.keys().collect::<Vec<_>>());
+                }
+
+                let result = match changes.kind() {
+                    // Swapping stylesheets in place keeps React state
+                    Some(ChangeKind::Style) => {
+                        println!("CSS changed, hot-swapping: {}", changes.describe());
+                        windows.evaluate_all(&watcher::stylesheet_swap_script(
+                            &changes.stylesheets(&asset_root),
+                        ))
+                    }
+                    // Reloads wait for each page to stash its state in Rust first
+                    Some(kind) => {
+                        match kind {
+                            ChangeKind::Script => {
+                                println!("JS changed, reloading page: {}", changes.describe())
+                            }
+                            _ => println!("HTML changed, navigating again: {}", changes.describe()),
+                        }
+                        let deadline = Instant::now() + state_stash::STASH_TIMEOUT;
+                        for (label, _) in windows.iter() {
+                            pending_reloads.insert(label.to_string(), (kind, deadline));
+                        }
+                        windows.evaluate_all(state_stash::STASH_SCRIPT)
+                    }
+                    None => Ok(()),
+                };
+                if let Err(e) = result {
+                    eprintln!("Failed to apply hot update: {}", e);
+                }
+                for (_, managed) in windows.iter() {
+                    managed.window.request_redraw();
+                }
+            }
+            Event::UserEvent(UserEvent::StateStashed(label)) => {
+                if let Some((kind, _)) = pending_reloads.remove(&label) {
+                    if let Some(managed) = windows.get(&label) {
+                        if let Err(e) = reload_page(&managed.webview, kind) {
+                            eprintln!("Failed to reload page: {}", e);
+                        }
                     }
-                    window.request_redraw();
                 }
             }
+            Event::NewEvents(StartCause::ResumeTimeReached { .. }) => {
+                let now = Instant::now();
+                pending_reloads.retain(|label, (kind, deadline)| {
+                    if now < *deadline {
+                        return true;
+                    }
+                    eprintln!("Page did not stash its state in time, reloading anyway");
+                    if let Some(managed) = windows.get(label) {
+                        if let Err(e) = reload_page(&managed.webview, *kind) {
+                            eprintln!("Failed to reload page: {}", e);
+                        }
+                    }
+                    false
+                });
+            }
             _ => (),
         }
+
+        // Closing the main window quits, whichever way it was closed, and so does Quit once the
+        // guarded windows have closed. Everything shuts down in order: hooks, IPC and its
+        // runtime, the watcher, Vite, then the windows and their state.
+        if shutdown_requested || windows.quit_ready() || windows.get(MAIN_WINDOW).is_none() {
+            let deadline = Instant::now() + shutdown::SHUTDOWN_TIMEOUT;
+            mem::take(&mut shutdown_hooks).run(deadline);
+            if let Some(server) = ipc_server.take() {
+                server.shutdown(deadline);
+            }
+            if let Some(watcher) = watcher.take() {
+                watcher.stop();
+            }
+            if let Some(mut server) = dev_server.take() {
+                server.stop();
+            }
+            windows.close_all();
+            *control_flow = ControlFlow::Exit;
+            return;
+        }
+
+        // Sleep until the next event, or until a pending reload stops waiting for its stash
+        *control_flow = match pending_reloads
+            .values()
+            .map(|(_, deadline)| *deadline)
+            .min()
+        {
+            Some(deadline) => ControlFlow::WaitUntil(deadline),
+            None => ControlFlow::Wait,
+        };
     });
 }
@@ -0,0 +1,181 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::Path,
+};
+
+use serde::Deserialize;
+
+// Where Vite 5 writes the manifest, and where Vite 4 used to
+pub const MANIFEST_PATHS: [&str; 2] = [".vite/manifest.json", "manifest.json"];
+
+// One entry of Vite's build manifest, see https://vite.dev/guide/backend-integration
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestChunk {
+    pub file: String,
+    #[serde(default)]
+    pub is_entry: bool,
+    #[serde(default)]
+    pub css: Vec<String>,
+    #[serde(default)]
+    pub imports: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct ViteManifest {
+    chunks: HashMap<String, ManifestChunk>,
+}
+
+// The files an entry point needs, in the order they should be loaded
+#[derive(Debug, Default)]
+pub struct EntryAssets {
+    pub script: String,
+    pub styles: Vec<String>,
+    pub preloads: Vec<String>,
+}
+
+impl ViteManifest {
+    pub fn load(path: &Path) -> Result<Self, io::Error> {
+        let content = fs::read(path)?;
+        let chunks = serde_json::from_slice(&content)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(Self { chunks })
+    }
+
+    pub fn entry(&self, name: &str) -> Option<EntryAssets> {
+        let chunk = self.chunks.get(name).filter(|chunk| chunk.is_entry)?;
+
+        let mut assets = EntryAssets {
+            script: chunk.file.clone(),
+            styles: chunk.css.clone(),
+            preloads: Vec::new(),
+        };
+
+        // Walk static imports so every chunk the entry pulls in is preloaded up front
+        let mut seen = HashSet::new();
+        let mut pending: Vec<&str> = chunk.imports.iter().map(String::as_str).collect();
+        while let Some(key) = pending.pop() {
+            if !seen.insert(key) {
+                continue;
+            }
+            let Some(imported) = self.chunks.get(key) else {
+                eprintln!("Vite manifest references missing chunk {}", key);
+                continue;
+            };
+
+            assets.preloads.push(imported.file.clone());
+            for css in &imported.css {
+                if !assets.styles.contains(css) {
+                    assets.styles.push(css.clone());
+                }
+            }
+            pending.extend(imported.imports.iter().map(String::as_str));
+        }
+
+        Some(assets)
+    }
+}
+
+impl EntryAssets {
+    pub fn to_html(&self, base_url: &str, nonce: &str) -> String {
+        let mut html = String::new();
+        for style in &self.styles {
+            html.push_str(&format!(
+                r#"<link rel="stylesheet" crossorigin nonce="{}" href="{}{}">"#,
+                nonce, base_url, style
+            ));
+        }
+        for preload in &self.preloads {
+            html.push_str(&format!(
+                r#"<link rel="modulepreload" crossorigin nonce="{}" href="{}{}">"#,
+                nonce, base_url, preload
+            ));
+        }
+        html.push_str(&format!(
+            r#"<script type="module" crossorigin nonce="{}" src="{}{}"></script>"#,
+            nonce, base_url, self.script
+        ));
+        html
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(json: &str) -> ViteManifest {
+        ViteManifest {
+            chunks: serde_json::from_str(json).unwrap(),
+        }
+    }
+
+    #[test]
+    fn walks_static_imports() {
+        let manifest = manifest(
+            r#"{
+                "index.html": {
+                    "file": "assets/index-a1.js",
+                    "isEntry": true,
+                    "css": ["assets/index-a1.css"],
+                    "imports": ["_vendor.js", "_shared.js"]
+                },
+                "_vendor.js": {
+                    "file": "assets/vendor-b2.js",
+                    "css": ["assets/vendor-b2.css"],
+                    "imports": ["_shared.js"]
+                },
+                "_shared.js": {
+                    "file": "assets/shared-c3.js",
+                    "css": ["assets/index-a1.css"],
+                    "imports": ["_vendor.js"]
+                }
+            }"#,
+        );
+        let entry = manifest.entry("index.html").unwrap();
+
+        assert_eq!(entry.script, "assets/index-a1.js");
+        assert_eq!(
+            entry.styles,
+            ["assets/index-a1.css", "assets/vendor-b2.css"]
+        );
+        // Each chunk is preloaded once, even though the imports form a cycle
+        let mut preloads = entry.preloads.clone();
+        preloads.sort();
+        assert_eq!(preloads, ["assets/shared-c3.js", "assets/vendor-b2.js"]);
+    }
+
+    #[test]
+    fn skips_missing_chunks_and_non_entries() {
+        let manifest = manifest(
+            r#"{
+                "index.html": { "file": "assets/index.js", "isEntry": true, "imports": ["_gone.js"] },
+                "_lazy.js": { "file": "assets/lazy.js" }
+            }"#,
+        );
+
+        assert!(manifest.entry("index.html").unwrap().preloads.is_empty());
+        assert!(manifest.entry("_lazy.js").is_none());
+        assert!(manifest.entry("missing.html").is_none());
+    }
+
+    #[test]
+    fn renders_tags_with_the_nonce() {
+        let entry = EntryAssets {
+            script: "assets/index.js".to_string(),
+            styles: vec!["assets/index.css".to_string()],
+            preloads: vec!["assets/vendor.js".to_string()],
+        };
+        let html = entry.to_html("assets://", "n0nce");
+
+        assert!(html.contains(
+            r#"rel="stylesheet" crossorigin nonce="n0nce" href="assets://assets/index.css""#
+        ));
+        assert!(html.contains(
+            r#"rel="modulepreload" crossorigin nonce="n0nce" href="assets://assets/vendor.js""#
+        ));
+        assert!(html.ends_with(
+            r#"<script type="module" crossorigin nonce="n0nce" src="assets://assets/index.js"></script>"#
+        ));
+    }
+}
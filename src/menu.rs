@@ -0,0 +1,880 @@
+use std::{collections::HashMap, error::Error, fmt, mem};
+
+use muda::{
+    accelerator::Accelerator, AboutMetadata, CheckMenuItem, IsMenuItem, Menu, MenuItem,
+    MenuItemKind, PredefinedMenuItem, Submenu,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{events, ipc, shortcuts::ShortcutRegistry};
+
+// IPC function used by the client script; it never reaches the ProtocolHandler
+const MENU_FUNCTION: &str = "__rust_gui_menu";
+
+// window.rustMenu.set(items) replaces the menu with entries shaped like MenuEntry, and
+// onClick(listener) hears about clicks, which also arrive as `rust-event` with event 'menu'
+pub const CLIENT_SCRIPT: &str = r#"
+(() => {
+    const send = (command) => window.ipc?.postMessage(
+        JSON.stringify({ function: '__rust_gui_menu', args: [JSON.stringify(command)] })
+    );
+    window.rustMenu = {
+        set(items) {
+            send({ command: 'set', items });
+        },
+        setEnabled(id, enabled) {
+            send({ command: 'set-enabled', id, enabled });
+        },
+        setChecked(id, checked) {
+            send({ command: 'set-checked', id, checked });
+        },
+        onClick(listener) {
+            const handler = (e) => {
+                if (e.detail?.event === 'menu') listener(e.detail.payload);
+            };
+            window.addEventListener('rust-event', handler);
+            return () => window.removeEventListener('rust-event', handler);
+        },
+    };
+})();
+"#;
+
+// One entry of the menu bar. Top-level entries must be submenus; ids name the items that
+// report clicks and can be updated later, so they must be unique.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum MenuEntry {
+    Submenu {
+        title: String,
+        items: Vec<MenuEntry>,
+        #[serde(default = "enabled")]
+        enabled: bool,
+    },
+    Item {
+        id: String,
+        title: String,
+        #[serde(default)]
+        accelerator: Option<String>,
+        #[serde(default = "enabled")]
+        enabled: bool,
+    },
+    Checkbox {
+        id: String,
+        title: String,
+        #[serde(default)]
+        checked: bool,
+        #[serde(default)]
+        accelerator: Option<String>,
+        #[serde(default = "enabled")]
+        enabled: bool,
+    },
+    Separator,
+    // Items the platform implements itself, such as Copy and Quit
+    Role {
+        role: MenuRole,
+    },
+    // Where the app-level shortcuts go, see ShortcutRegistry. A menu without one gets them at
+    // the end of its first submenu.
+    Shortcuts,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MenuRole {
+    About,
+    Hide,
+    HideOthers,
+    ShowAll,
+    Quit,
+    Undo,
+    Redo,
+    Cut,
+    Copy,
+    Paste,
+    SelectAll,
+    Minimize,
+    Maximize,
+    Fullscreen,
+    CloseWindow,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+impl MenuEntry {
+    pub fn submenu(title: &str, items: Vec<MenuEntry>) -> Self {
+        Self::Submenu {
+            title: title.to_string(),
+            items,
+            enabled: true,
+        }
+    }
+
+    pub fn item(id: &str, title: &str) -> Self {
+        Self::Item {
+            id: id.to_string(),
+            title: title.to_string(),
+            accelerator: None,
+            enabled: true,
+        }
+    }
+
+    pub fn role(role: MenuRole) -> Self {
+        Self::Role { role }
+    }
+
+    // Accelerators use muda's syntax, e.g. "CmdOrCtrl+Shift+R"; other entries ignore them
+    pub fn accelerator(mut self, shortcut: &str) -> Self {
+        if let Self::Item { accelerator, .. } | Self::Checkbox { accelerator, .. } = &mut self {
+            *accelerator = Some(shortcut.to_string());
+        }
+        self
+    }
+}
+
+// Id of the Quit item. The app handles it itself, so pages get to keep their windows open.
+pub const QUIT_ID: &str = "quit";
+
+// The menu the app starts with: the app, edit and window menus a macOS app is expected to have.
+// Elsewhere, only the items the platform can carry out.
+pub fn default_menu(title: &str) -> Vec<MenuEntry> {
+    if !cfg!(target_os = "macos") {
+        return menu_bar(title);
+    }
+    vec![
+        MenuEntry::submenu(
+            title,
+            vec![
+                MenuEntry::role(MenuRole::About),
+                MenuEntry::Separator,
+                MenuEntry::role(MenuRole::Hide),
+                MenuEntry::role(MenuRole::HideOthers),
+                MenuEntry::role(MenuRole::ShowAll),
+                MenuEntry::Separator,
+                MenuEntry::item(QUIT_ID, "Quit").accelerator("CmdOrCtrl+Q"),
+            ],
+        ),
+        edit_menu(),
+        view_menu(),
+        MenuEntry::submenu(
+            "Window",
+            vec![
+                MenuEntry::role(MenuRole::Minimize),
+                MenuEntry::role(MenuRole::CloseWindow),
+            ],
+        ),
+    ]
+}
+
+// GTK has no Hide or Window roles, and carries out the edit ones only through libxdo,
+// which muda is built without. Editing is left to the webview's own shortcuts and context menu.
+fn menu_bar(title: &str) -> Vec<MenuEntry> {
+    vec![
+        MenuEntry::submenu(
+            title,
+            vec![
+                MenuEntry::role(MenuRole::About),
+                MenuEntry::Separator,
+                MenuEntry::item(QUIT_ID, "Quit").accelerator("CmdOrCtrl+Q"),
+            ],
+        ),
+        view_menu(),
+    ]
+}
+
+fn edit_menu() -> MenuEntry {
+    MenuEntry::submenu(
+        "Edit",
+        vec![
+            MenuEntry::role(MenuRole::Undo),
+            MenuEntry::role(MenuRole::Redo),
+            MenuEntry::Separator,
+            MenuEntry::role(MenuRole::Cut),
+            MenuEntry::role(MenuRole::Copy),
+            MenuEntry::role(MenuRole::Paste),
+            MenuEntry::role(MenuRole::SelectAll),
+        ],
+    )
+}
+
+fn view_menu() -> MenuEntry {
+    MenuEntry::submenu(
+        "View",
+        vec![
+            MenuEntry::item("reload", "Reload").accelerator("CmdOrCtrl+R"),
+            MenuEntry::Separator,
+            MenuEntry::Shortcuts,
+        ],
+    )
+}
+
+// Menu requests from the frontend, see CLIENT_SCRIPT
+#[derive(Debug, Deserialize)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum MenuCommand {
+    Set { items: Vec<MenuEntry> },
+    SetEnabled { id: String, enabled: bool },
+    SetChecked { id: String, checked: bool },
+}
+
+// What the handlers and the frontend get for a click. Checkboxes have already toggled.
+#[derive(Debug, Clone, Serialize)]
+pub struct MenuClick {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checked: Option<bool>,
+}
+
+#[derive(Debug)]
+pub enum MenuError {
+    TopLevelItem(String),
+    DuplicateId(String),
+    UnknownId(String),
+    Accelerator { id: String, message: String },
+    AcceleratorConflict { id: String, existing: String },
+    Native(muda::Error),
+}
+
+impl fmt::Display for MenuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TopLevelItem(title) => {
+                write!(f, "Top-level menu entries must be submenus: {}", title)
+            }
+            Self::DuplicateId(id) => write!(f, "Menu item id used twice: {}", id),
+            Self::UnknownId(id) => write!(f, "No menu item with id: {}", id),
+            Self::Accelerator { id, message } => {
+                write!(f, "Invalid accelerator for menu item {}: {}", id, message)
+            }
+            Self::AcceleratorConflict { id, existing } => write!(
+                f,
+                "Menu item {} has the same accelerator as {}",
+                id, existing
+            ),
+            Self::Native(e) => write!(f, "Failed to build menu: {}", e),
+        }
+    }
+}
+
+impl Error for MenuError {}
+
+impl From<muda::Error> for MenuError {
+    fn from(e: muda::Error) -> Self {
+        Self::Native(e)
+    }
+}
+
+type MenuHandler = Box<dyn Fn(&MenuClick)>;
+
+// The native menu built from a list of entries, with its items by id and the Rust handlers
+// for their clicks. Lives on the event loop thread.
+pub struct AppMenu {
+    menu: Menu,
+    items: HashMap<String, MenuItemKind>,
+    handlers: HashMap<String, MenuHandler>,
+    shortcuts: ShortcutRegistry,
+    about: AboutMetadata,
+}
+
+impl AppMenu {
+    // The shortcuts are placed in whatever menu they are combined with, and About shows the
+    // app's name and version
+    pub fn build(
+        entries: &[MenuEntry],
+        shortcuts: ShortcutRegistry,
+        app_name: &str,
+    ) -> Result<Self, MenuError> {
+        let about = AboutMetadata {
+            name: Some(app_name.to_string()),
+            version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            ..AboutMetadata::default()
+        };
+        let (menu, items) = build_menu(entries, &shortcuts, &about)?;
+        Ok(Self {
+            menu,
+            items,
+            handlers: HashMap::new(),
+            shortcuts,
+            about,
+        })
+    }
+
+    pub fn menu(&self) -> &Menu {
+        &self.menu
+    }
+
+    // Handlers stay registered when the frontend replaces the menu
+    pub fn on<F: Fn(&MenuClick) + 'static>(&mut self, id: &str, handler: F) {
+        self.handlers.insert(id.to_string(), Box::new(handler));
+    }
+
+    // Swaps in a new menu; the windows still have the old one until it is set on them
+    pub fn replace(&mut self, entries: &[MenuEntry]) -> Result<(), MenuError> {
+        let (menu, items) = build_menu(entries, &self.shortcuts, &self.about)?;
+        self.items = items;
+        self.menu = menu;
+        Ok(())
+    }
+
+    pub fn set_enabled(&self, id: &str, enabled: bool) -> Result<(), MenuError> {
+        match self.items.get(id) {
+            Some(MenuItemKind::MenuItem(item)) => item.set_enabled(enabled),
+            Some(MenuItemKind::Check(item)) => item.set_enabled(enabled),
+            _ => return Err(MenuError::UnknownId(id.to_string())),
+        }
+        Ok(())
+    }
+
+    pub fn set_checked(&self, id: &str, checked: bool) -> Result<(), MenuError> {
+        match self.items.get(id) {
+            Some(MenuItemKind::Check(item)) => item.set_checked(checked),
+            _ => return Err(MenuError::UnknownId(id.to_string())),
+        }
+        Ok(())
+    }
+
+    // Runs the Rust side of a click and returns the script that tells the frontend about it.
+    // None for clicks the frontend doesn't hear about, such as roles or Rust-only shortcuts.
+    pub fn click(&self, id: &str) -> Option<String> {
+        if let Some(shortcut) = self.shortcuts.get(id) {
+            return shortcut.trigger();
+        }
+
+        let item = self.items.get(id)?;
+        let click = MenuClick {
+            id: id.to_string(),
+            checked: item.as_check_menuitem().map(CheckMenuItem::is_checked),
+        };
+        if let Some(handler) = self.handlers.get(id) {
+            handler(&click);
+        }
+        Some(events::dispatch_script("menu", &click))
+    }
+}
+
+// Picks the client script's menu commands out of the IPC traffic
+pub fn command(body: &str) -> Option<MenuCommand> {
+    ipc::command(body, MENU_FUNCTION)
+}
+
+fn build_menu(
+    entries: &[MenuEntry],
+    shortcuts: &ShortcutRegistry,
+    about: &AboutMetadata,
+) -> Result<(Menu, HashMap<String, MenuItemKind>), MenuError> {
+    let mut builder = MenuBuilder {
+        items: HashMap::new(),
+        accelerators: HashMap::new(),
+        shortcuts: shortcuts.menu_items(),
+        about,
+    };
+    let menu = Menu::new();
+    let mut first = None;
+    for entry in entries {
+        if !matches!(entry, MenuEntry::Submenu { .. }) {
+            return Err(MenuError::TopLevelItem(title(entry)));
+        }
+        if let Some(MenuItemKind::Submenu(submenu)) = builder.entry(entry)? {
+            menu.append(&submenu)?;
+            first.get_or_insert(submenu);
+        }
+    }
+
+    // No `shortcuts` entry took them
+    if let Some(first) = first.filter(|_| !builder.shortcuts.is_empty()) {
+        first.append(&PredefinedMenuItem::separator())?;
+        for entry in mem::take(&mut builder.shortcuts) {
+            if let Some(child) = builder.entry(&entry)? {
+                first.append(as_dyn(&child))?;
+            }
+        }
+    }
+    Ok((menu, builder.items))
+}
+
+// The items built so far by id, and the accelerators taken, to catch the ones used twice
+struct MenuBuilder<'a> {
+    items: HashMap<String, MenuItemKind>,
+    accelerators: HashMap<Accelerator, String>,
+    // Not placed yet; they go where the first `shortcuts` entry is
+    shortcuts: Vec<MenuEntry>,
+    about: &'a AboutMetadata,
+}
+
+impl MenuBuilder<'_> {
+    // None for entries the platform can't show, including submenus left with nothing in them
+    fn entry(&mut self, entry: &MenuEntry) -> Result<Option<MenuItemKind>, MenuError> {
+        let kind = match entry {
+            MenuEntry::Submenu {
+                title,
+                items: entries,
+                enabled,
+            } => {
+                let submenu = Submenu::new(title, *enabled);
+                let mut empty = true;
+                for entry in entries {
+                    let children = match entry {
+                        MenuEntry::Shortcuts => mem::take(&mut self.shortcuts),
+                        entry => vec![entry.clone()],
+                    };
+                    for entry in &children {
+                        if let Some(child) = self.entry(entry)? {
+                            empty &= matches!(entry, MenuEntry::Separator);
+                            submenu.append(as_dyn(&child))?;
+                        }
+                    }
+                }
+                return Ok((!empty).then_some(MenuItemKind::Submenu(submenu)));
+            }
+            MenuEntry::Item {
+                id,
+                title,
+                accelerator,
+                enabled,
+            } => MenuItemKind::MenuItem(MenuItem::with_id(
+                id.as_str(),
+                title,
+                *enabled,
+                self.accelerator(id, accelerator.as_deref())?,
+            )),
+            MenuEntry::Checkbox {
+                id,
+                title,
+                checked,
+                accelerator,
+                enabled,
+            } => MenuItemKind::Check(CheckMenuItem::with_id(
+                id.as_str(),
+                title,
+                *enabled,
+                *checked,
+                self.accelerator(id, accelerator.as_deref())?,
+            )),
+            MenuEntry::Separator => {
+                return Ok(Some(MenuItemKind::Predefined(
+                    PredefinedMenuItem::separator(),
+                )))
+            }
+            MenuEntry::Role { role } if !role.is_native() => return Ok(None),
+            MenuEntry::Role { role } => {
+                // The item brings its own accelerator, which other items can't take
+                if let Some(accelerator) = role.accelerator() {
+                    let accelerator = accelerator.parse().expect("Role accelerators are valid");
+                    self.take(&title(entry), accelerator)?;
+                }
+                return Ok(Some(MenuItemKind::Predefined(predefined(
+                    *role, self.about,
+                ))));
+            }
+            // Expanded by the submenu it is in
+            MenuEntry::Shortcuts => return Ok(None),
+        };
+
+        let id = kind.id().0.clone();
+        if self.items.contains_key(&id) {
+            return Err(MenuError::DuplicateId(id));
+        }
+        self.items.insert(id, kind.clone());
+        Ok(Some(kind))
+    }
+
+    fn accelerator(
+        &mut self,
+        id: &str,
+        accelerator: Option<&str>,
+    ) -> Result<Option<Accelerator>, MenuError> {
+        let Some(accelerator) = accelerator else {
+            return Ok(None);
+        };
+        let parsed: Accelerator =
+            accelerator
+                .parse()
+                .map_err(
+                    |e: muda::accelerator::AcceleratorParseError| MenuError::Accelerator {
+                        id: id.to_string(),
+                        message: e.to_string(),
+                    },
+                )?;
+        self.take(id, parsed)?;
+        Ok(Some(parsed))
+    }
+
+    fn take(&mut self, id: &str, accelerator: Accelerator) -> Result<(), MenuError> {
+        if let Some(existing) = self.accelerators.insert(accelerator, id.to_string()) {
+            return Err(MenuError::AcceleratorConflict {
+                id: id.to_string(),
+                existing,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl MenuRole {
+    // The one muda gives the role's item on this platform
+    fn accelerator(self) -> Option<&'static str> {
+        match self {
+            Self::Hide => Some("CmdOrCtrl+H"),
+            Self::HideOthers => Some("CmdOrCtrl+Alt+H"),
+            Self::Quit if cfg!(target_os = "macos") => Some("CmdOrCtrl+Q"),
+            Self::Undo => Some("CmdOrCtrl+Z"),
+            Self::Redo if cfg!(target_os = "macos") => Some("CmdOrCtrl+Shift+Z"),
+            Self::Redo => Some("CmdOrCtrl+Y"),
+            Self::Cut => Some("CmdOrCtrl+X"),
+            Self::Copy => Some("CmdOrCtrl+C"),
+            Self::Paste => Some("CmdOrCtrl+V"),
+            Self::SelectAll => Some("CmdOrCtrl+A"),
+            Self::Minimize => Some("CmdOrCtrl+M"),
+            Self::Fullscreen if cfg!(target_os = "macos") => Some("Ctrl+Super+F"),
+            Self::CloseWindow if cfg!(target_os = "macos") => Some("CmdOrCtrl+W"),
+            Self::CloseWindow => Some("Alt+F4"),
+            _ => None,
+        }
+    }
+
+    // GTK skips all but these
+    fn is_native(self) -> bool {
+        !cfg!(target_os = "linux")
+            || matches!(
+                self,
+                Self::About | Self::Cut | Self::Copy | Self::Paste | Self::SelectAll
+            )
+    }
+}
+
+fn predefined(role: MenuRole, about: &AboutMetadata) -> PredefinedMenuItem {
+    match role {
+        MenuRole::About => PredefinedMenuItem::about(None, Some(about.clone())),
+        MenuRole::Hide => PredefinedMenuItem::hide(None),
+        MenuRole::HideOthers => PredefinedMenuItem::hide_others(None),
+        MenuRole::ShowAll => PredefinedMenuItem::show_all(None),
+        MenuRole::Quit => PredefinedMenuItem::quit(None),
+        MenuRole::Undo => PredefinedMenuItem::undo(None),
+        MenuRole::Redo => PredefinedMenuItem::redo(None),
+        MenuRole::Cut => PredefinedMenuItem::cut(None),
+        MenuRole::Copy => PredefinedMenuItem::copy(None),
+        MenuRole::Paste => PredefinedMenuItem::paste(None),
+        MenuRole::SelectAll => PredefinedMenuItem::select_all(None),
+        MenuRole::Minimize => PredefinedMenuItem::minimize(None),
+        MenuRole::Maximize => PredefinedMenuItem::maximize(None),
+        MenuRole::Fullscreen => PredefinedMenuItem::fullscreen(None),
+        MenuRole::CloseWindow => PredefinedMenuItem::close_window(None),
+    }
+}
+
+fn as_dyn(kind: &MenuItemKind) -> &dyn IsMenuItem {
+    match kind {
+        MenuItemKind::MenuItem(item) => item,
+        MenuItemKind::Submenu(item) => item,
+        MenuItemKind::Predefined(item) => item,
+        MenuItemKind::Check(item) => item,
+        MenuItemKind::Icon(item) => item,
+    }
+}
+
+fn title(entry: &MenuEntry) -> String {
+    match entry {
+        MenuEntry::Submenu { title, .. }
+        | MenuEntry::Item { title, .. }
+        | MenuEntry::Checkbox { title, .. } => title.clone(),
+        MenuEntry::Separator => "separator".to_string(),
+        MenuEntry::Role { role } => format!("{:?}", role),
+        MenuEntry::Shortcuts => "shortcuts".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shortcuts::ShortcutAction;
+
+    fn ipc(function: &str, command: &str) -> String {
+        serde_json::json!({ "function": function, "args": [command] }).to_string()
+    }
+
+    fn build(entries: &[MenuEntry]) -> Result<HashMap<String, MenuItemKind>, MenuError> {
+        build_menu(entries, &ShortcutRegistry::new(), &AboutMetadata::default())
+            .map(|(_, items)| items)
+    }
+
+    fn palette() -> ShortcutRegistry {
+        let mut shortcuts = ShortcutRegistry::new();
+        shortcuts
+            .register(
+                "palette",
+                "Command Palette",
+                "CmdOrCtrl+K",
+                ShortcutAction::Emit,
+            )
+            .unwrap();
+        shortcuts
+    }
+
+    fn item_ids(submenu: &Submenu) -> Vec<String> {
+        submenu
+            .items()
+            .iter()
+            .filter(|item| item.as_predefined_menuitem().is_none())
+            .map(|item| item.id().0.clone())
+            .collect()
+    }
+
+    fn submenus(menu: &Menu) -> Vec<Submenu> {
+        menu.items()
+            .into_iter()
+            .filter_map(|item| item.as_submenu().cloned())
+            .collect()
+    }
+
+    #[test]
+    fn entries_round_trip_through_json() {
+        let entries = vec![MenuEntry::submenu(
+            "File",
+            vec![
+                MenuEntry::item("new", "New").accelerator("CmdOrCtrl+N"),
+                MenuEntry::Separator,
+                MenuEntry::Checkbox {
+                    id: "autosave".to_string(),
+                    title: "Autosave".to_string(),
+                    checked: true,
+                    accelerator: None,
+                    enabled: false,
+                },
+                MenuEntry::role(MenuRole::SelectAll),
+            ],
+        )];
+        let json = serde_json::to_string(&entries).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Vec<MenuEntry>>(&json).unwrap(),
+            entries
+        );
+    }
+
+    #[test]
+    fn entries_from_the_frontend_get_defaults() {
+        let entry: MenuEntry = serde_json::from_str(
+            r#"{ "type": "submenu", "title": "File", "items": [
+                { "type": "item", "id": "new", "title": "New" },
+                { "type": "role", "role": "hide-others" }
+            ] }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            entry,
+            MenuEntry::submenu(
+                "File",
+                vec![
+                    MenuEntry::item("new", "New"),
+                    MenuEntry::role(MenuRole::HideOthers)
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn picks_menu_commands_out_of_ipc_traffic() {
+        let set = ipc(MENU_FUNCTION, r#"{ "command": "set", "items": [] }"#);
+        assert!(matches!(command(&set), Some(MenuCommand::Set { items }) if items.is_empty()));
+
+        let enabled = ipc(
+            MENU_FUNCTION,
+            r#"{ "command": "set-enabled", "id": "new", "enabled": false }"#,
+        );
+        assert!(matches!(
+            command(&enabled),
+            Some(MenuCommand::SetEnabled { id, enabled: false }) if id == "new"
+        ));
+
+        let checked = ipc(
+            MENU_FUNCTION,
+            r#"{ "command": "set-checked", "id": "autosave", "checked": true }"#,
+        );
+        assert!(matches!(
+            command(&checked),
+            Some(MenuCommand::SetChecked { id, checked: true }) if id == "autosave"
+        ));
+    }
+
+    #[test]
+    fn leaves_other_ipc_traffic_alone() {
+        assert!(command(&ipc("greet", r#"{ "command": "set", "items": [] }"#)).is_none());
+        assert!(command(&ipc(MENU_FUNCTION, r#"{ "command": "explode" }"#)).is_none());
+        assert!(command("not json").is_none());
+    }
+
+    #[test]
+    fn builds_the_default_menu() {
+        let items = build(&default_menu("App")).unwrap();
+        assert!(items.contains_key("reload"));
+        assert!(items.contains_key(QUIT_ID));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn builds_a_menu_gtk_can_show() {
+        let entries = default_menu("App");
+        let (menu, items) = build_menu(&entries, &palette(), &AboutMetadata::default()).unwrap();
+        let submenus = submenus(&menu);
+        assert_eq!(submenus.len(), 2);
+        assert_eq!(item_ids(&submenus[0]), [QUIT_ID]);
+        assert_eq!(item_ids(&submenus[1]), ["reload", "palette"]);
+        assert_eq!(items.len(), 3);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn drops_submenus_gtk_would_leave_empty() {
+        let entries = [
+            MenuEntry::submenu("File", vec![MenuEntry::item("new", "New")]),
+            MenuEntry::submenu(
+                "Window",
+                vec![
+                    MenuEntry::role(MenuRole::Minimize),
+                    MenuEntry::Separator,
+                    MenuEntry::role(MenuRole::CloseWindow),
+                ],
+            ),
+        ];
+        let (menu, _) = build_menu(&entries, &palette(), &AboutMetadata::default()).unwrap();
+        let submenus = submenus(&menu);
+        assert_eq!(submenus.len(), 1);
+        assert_eq!(item_ids(&submenus[0]), ["new", "palette"]);
+    }
+
+    #[test]
+    fn rejects_top_level_items() {
+        let result = build(&[MenuEntry::item("new", "New")]);
+        assert!(matches!(result, Err(MenuError::TopLevelItem(title)) if title == "New"));
+    }
+
+    #[test]
+    fn rejects_duplicate_ids_across_submenus() {
+        let result = build(&[
+            MenuEntry::submenu("File", vec![MenuEntry::item("open", "Open")]),
+            MenuEntry::submenu(
+                "Recent",
+                vec![MenuEntry::submenu(
+                    "Files",
+                    vec![MenuEntry::item("open", "Open")],
+                )],
+            ),
+        ]);
+        assert!(matches!(result, Err(MenuError::DuplicateId(id)) if id == "open"));
+    }
+
+    #[test]
+    fn rejects_invalid_and_conflicting_accelerators() {
+        let result = build(&[MenuEntry::submenu(
+            "File",
+            vec![MenuEntry::item("new", "New").accelerator("Ctrl+Nope")],
+        )]);
+        assert!(matches!(result, Err(MenuError::Accelerator { id, .. }) if id == "new"));
+
+        // The same key combination, spelled differently
+        let result = build(&[MenuEntry::submenu(
+            "File",
+            vec![
+                MenuEntry::item("new", "New").accelerator("Ctrl+Shift+N"),
+                MenuEntry::item("window", "New Window").accelerator("shift+ctrl+n"),
+            ],
+        )]);
+        assert!(matches!(
+            result,
+            Err(MenuError::AcceleratorConflict { id, existing }) if id == "window" && existing == "new"
+        ));
+    }
+
+    #[test]
+    fn places_shortcuts_at_the_shortcuts_entry() {
+        let entries = [
+            MenuEntry::submenu("File", vec![MenuEntry::item("new", "New")]),
+            MenuEntry::submenu(
+                "Go",
+                vec![MenuEntry::Shortcuts, MenuEntry::item("back", "Back")],
+            ),
+        ];
+        let (menu, items) = build_menu(&entries, &palette(), &AboutMetadata::default()).unwrap();
+        assert!(items.contains_key("palette"));
+        let submenus = submenus(&menu);
+        assert_eq!(submenus.len(), 2);
+        assert_eq!(item_ids(&submenus[0]), ["new"]);
+        assert_eq!(item_ids(&submenus[1]), ["palette", "back"]);
+    }
+
+    #[test]
+    fn places_shortcuts_in_the_first_submenu_without_an_entry() {
+        let entries = [
+            MenuEntry::submenu("App", vec![MenuEntry::item("about", "About")]),
+            MenuEntry::submenu("File", vec![MenuEntry::item("new", "New")]),
+        ];
+        let (menu, _) = build_menu(&entries, &palette(), &AboutMetadata::default()).unwrap();
+        let submenus = submenus(&menu);
+        assert_eq!(item_ids(&submenus[0]), ["about", "palette"]);
+        assert_eq!(item_ids(&submenus[1]), ["new"]);
+    }
+
+    #[test]
+    fn rejects_menu_items_that_reuse_a_shortcut() {
+        let entries = [MenuEntry::submenu(
+            "Edit",
+            vec![MenuEntry::item("find", "Find").accelerator("CmdOrCtrl+K")],
+        )];
+        let result = build_menu(&entries, &palette(), &AboutMetadata::default());
+        assert!(matches!(
+            result,
+            Err(MenuError::AcceleratorConflict { id, existing }) if id == "palette" && existing == "find"
+        ));
+    }
+
+    #[test]
+    fn rejects_shortcuts_that_clash_with_a_role() {
+        let mut shortcuts = ShortcutRegistry::new();
+        shortcuts
+            .register(
+                "copy-link",
+                "Copy Link",
+                "CmdOrCtrl+C",
+                ShortcutAction::Emit,
+            )
+            .unwrap();
+        let entries = [edit_menu(), view_menu()];
+        let result = build_menu(&entries, &shortcuts, &AboutMetadata::default());
+        assert!(matches!(
+            result,
+            Err(MenuError::AcceleratorConflict { id, existing }) if id == "copy-link" && existing == "Copy"
+        ));
+    }
+
+    #[test]
+    fn takes_the_accelerators_of_every_role() {
+        let roles = [
+            MenuRole::About,
+            MenuRole::Hide,
+            MenuRole::HideOthers,
+            MenuRole::ShowAll,
+            MenuRole::Quit,
+            MenuRole::Undo,
+            MenuRole::Redo,
+            MenuRole::Cut,
+            MenuRole::Copy,
+            MenuRole::Paste,
+            MenuRole::SelectAll,
+            MenuRole::Minimize,
+            MenuRole::Maximize,
+            MenuRole::Fullscreen,
+            MenuRole::CloseWindow,
+        ];
+        let entries = [MenuEntry::submenu(
+            "All",
+            roles.into_iter().map(MenuEntry::role).collect(),
+        )];
+        assert!(build(&entries).is_ok());
+    }
+
+    #[test]
+    fn rejects_a_top_level_shortcuts_entry() {
+        let result = build(&[MenuEntry::Shortcuts]);
+        assert!(matches!(result, Err(MenuError::TopLevelItem(title)) if title == "shortcuts"));
+    }
+}
@@ -0,0 +1,97 @@
+use std::{fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+// Written to the output directory by frontend/plugins/build-errors.js when a Vite build fails
+pub const BUILD_ERROR_FILE: &str = "build-error.json";
+
+// Error overlay drawn over the page by injected script, so it works whatever the frontend renders.
+// Styles are set through the CSSOM, which the strict CSP allows without a nonce.
+const OVERLAY_ID: &str = "__rust_gui_error_overlay";
+
+// One error shown in the overlay; a reload of the page dismisses them all
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ErrorReport {
+    pub source: String,
+    #[serde(default)]
+    pub file: Option<String>,
+    #[serde(default)]
+    pub line: Option<u32>,
+    #[serde(default)]
+    pub column: Option<u32>,
+    pub message: String,
+    #[serde(default)]
+    pub stack: Option<String>,
+}
+
+impl ErrorReport {
+    pub fn new(source: &str, message: impl Into<String>) -> Self {
+        Self {
+            source: source.to_string(),
+            message: message.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, io::Error> {
+        let content = fs::read(path)?;
+        serde_json::from_slice(&content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+// Adds the report to the overlay, creating the overlay if it isn't showing yet
+pub fn show_script(report: &ErrorReport) -> String {
+    format!(
+        r#"
+        (() => {{
+            const report = {report};
+            let overlay = document.getElementById('{id}');
+            if (!overlay) {{
+                overlay = document.createElement('div');
+                overlay.id = '{id}';
+                Object.assign(overlay.style, {{
+                    position: 'fixed', inset: '0', zIndex: '2147483647', overflow: 'auto',
+                    padding: '24px', background: 'rgba(24, 24, 27, 0.96)', color: '#fafafa',
+                    font: '13px/1.5 ui-monospace, Menlo, monospace', userSelect: 'text',
+                }});
+                const close = document.createElement('button');
+                close.textContent = 'Dismiss';
+                Object.assign(close.style, {{ position: 'absolute', top: '16px', right: '16px' }});
+                close.addEventListener('click', () => overlay.remove());
+                overlay.append(close);
+                document.documentElement.appendChild(overlay);
+            }}
+
+            const entry = document.createElement('section');
+            Object.assign(entry.style, {{ marginBottom: '24px' }});
+            const heading = document.createElement('div');
+            heading.textContent = report.source + ' error';
+            Object.assign(heading.style, {{ color: '#f87171', fontWeight: 'bold' }});
+            entry.append(heading);
+            if (report.file) {{
+                const file = document.createElement('div');
+                file.textContent = [report.file, report.line, report.column].filter((part) => part != null).join(':');
+                Object.assign(file.style, {{ color: '#a1a1aa' }});
+                entry.append(file);
+            }}
+            const message = document.createElement('pre');
+            message.textContent = report.message;
+            Object.assign(message.style, {{ margin: '8px 0', whiteSpace: 'pre-wrap' }});
+            entry.append(message);
+            if (report.stack) {{
+                const stack = document.createElement('pre');
+                stack.textContent = report.stack;
+                Object.assign(stack.style, {{ margin: '0', whiteSpace: 'pre-wrap', color: '#a1a1aa' }});
+                entry.append(stack);
+            }}
+            overlay.append(entry);
+        }})();
+        "#,
+        id = OVERLAY_ID,
+        report = serde_json::to_string(report).unwrap_or_default(),
+    )
+}
+
+pub fn hide_script() -> String {
+    format!("document.getElementById('{}')?.remove();", OVERLAY_ID)
+}
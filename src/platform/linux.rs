@@ -0,0 +1,45 @@
+use muda::Menu;
+use tao::{platform::unix::WindowExtUnix, window::Window};
+use wry::{WebView, WebViewBuilder, WebViewBuilderExtUnix};
+
+use super::Platform;
+use crate::config::WindowConfig;
+
+// GTK, through tao's window and the glib application name
+pub struct Gtk;
+
+impl Platform for Gtk {
+    // GTK has no activation policy; presenting the window is enough
+    fn activate(&self) {}
+
+    // The window builder covers everything GTK lets an app decide; the compositor
+    // draws shadows and rounds corners, so those settings have no effect here
+    fn style_window(&self, _window: &Window, _config: &WindowConfig) {}
+
+    // Names the app in docks and switchers
+    fn set_app_name(&self, title: &str) {
+        gtk::glib::set_application_name(title);
+        gtk::glib::set_prgname(Some(title));
+    }
+
+    // There is no global menu bar, so the menu goes above the webview in the window's box
+    fn set_menu(&self, menu: &Menu, window: &Window) -> muda::Result<()> {
+        menu.init_for_gtk_window(window.gtk_window(), window.default_vbox())
+    }
+
+    fn remove_menu(&self, menu: &Menu, window: &Window) -> muda::Result<()> {
+        menu.remove_for_gtk_window(window.gtk_window())
+    }
+
+    // build() only supports X11; the GTK container works on Wayland too
+    fn build_webview<'a>(
+        &self,
+        builder: WebViewBuilder<'a>,
+        window: &'a Window,
+    ) -> wry::Result<WebView> {
+        match window.default_vbox() {
+            Some(vbox) => builder.build_gtk(vbox),
+            None => builder.build_gtk(window.gtk_window()),
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use cocoa::appkit::{
+    NSApplication, NSApplicationActivationPolicy::NSApplicationActivationPolicyRegular,
+    NSBackingStoreType, NSWindowButton,
+};
+use cocoa::base::{id, nil, NO, YES};
+use cocoa::foundation::{NSAutoreleasePool, NSString};
+use muda::Menu;
+use objc::{class, msg_send};
+use objc::{sel, sel_impl};
+use tao::platform::macos::WindowExtMacOS;
+use wry::{WebView, WebViewBuilder};
+
+use super::Platform;
+use crate::config::WindowConfig;
+
+// AppKit window chrome through cocoa and objc
+pub struct MacOs;
+
+impl Platform for MacOs {
+    fn activate(&self) {
+        unsafe {
+            let app = NSApplication::sharedApplication(nil);
+            app.setActivationPolicy_(NSApplicationActivationPolicyRegular);
+            app.activateIgnoringOtherApps_(YES);
+        }
+    }
+
+    fn style_window(&self, window: &tao::window::Window, config: &WindowConfig) {
+        unsafe { style_window(window, config) }
+    }
+
+    // The app menu takes its title from the process name
+    fn set_app_name(&self, title: &str) {
+        set_process_name(title)
+    }
+
+    // The menu bar belongs to the app rather than a window
+    fn set_menu(&self, menu: &Menu, _window: &tao::window::Window) -> muda::Result<()> {
+        menu.init_for_nsapp();
+        Ok(())
+    }
+
+    fn remove_menu(&self, menu: &Menu, _window: &tao::window::Window) -> muda::Result<()> {
+        menu.remove_for_nsapp();
+        Ok(())
+    }
+
+    fn build_webview<'a>(
+        &self,
+        builder: WebViewBuilder<'a>,
+        window: &'a tao::window::Window,
+    ) -> wry::Result<WebView> {
+        builder.build(window)
+    }
+}
+
+fn set_process_name(title: &str) {
+    unsafe {
+        let _pool = NSAutoreleasePool::new(nil);
+        let title_str = NSString::alloc(nil).init_str(title).autorelease();
+        let process_info: id = msg_send![class!(NSProcessInfo), processInfo];
+        let _: () = msg_send![process_info, setProcessName:title_str];
+    }
+}
+
+// tao has already set the style mask from the decorations, resizable and minimizable
+// settings; this adds what it has no options for, without touching the mask again
+unsafe fn style_window(window: &tao::window::Window, config: &WindowConfig) {
+    let ns_window: id = window.ns_window() as id;
+    let clear_color: id = msg_send![class!(NSColor), clearColor];
+
+    let _: () = msg_send![ns_window, setMovableByWindowBackground: YES];
+    let _: () = msg_send![ns_window, setHasShadow: if config.shadow { YES } else { NO }];
+    if !config.decorations {
+        let _: () = msg_send![ns_window, setTitlebarAppearsTransparent: YES];
+        let _: () = msg_send![ns_window, setTitleVisibility: 1];
+    }
+
+    if let Some(color) = config.background_color {
+        let channel = |value: u8| value as f64 / 255.0;
+        let background: id = msg_send![class!(NSColor),
+            colorWithCalibratedRed:channel(color.red)
+            green:channel(color.green)
+            blue:channel(color.blue)
+            alpha:channel(color.alpha)];
+        let _: () = msg_send![ns_window, setBackgroundColor: background];
+        let _: () = msg_send![ns_window, setOpaque: if color.alpha == 255 { YES } else { NO }];
+    }
+    let _: () = msg_send![ns_window, setBackingType: NSBackingStoreType::NSBackingStoreBuffered];
+
+    // Round the content view, and the frame view the title bar buttons live in
+    let content_view: id = msg_send![ns_window, contentView];
+    let _: () = msg_send![content_view, setWantsLayer: YES];
+    let layer: id = msg_send![content_view, layer];
+    let _: () = msg_send![layer, setBackgroundColor: clear_color];
+    let _: () = msg_send![layer, setMasksToBounds: YES];
+    let _: () = msg_send![layer, setCornerRadius: config.corner_radius];
+
+    let close_button: id =
+        msg_send![ns_window, standardWindowButton:NSWindowButton::NSWindowCloseButton];
+    if close_button != nil {
+        let superview: id = msg_send![close_button, superview];
+        if superview != nil {
+            let frame_layer: id = msg_send![superview, layer];
+            let _: () = msg_send![frame_layer, setCornerRadius: config.corner_radius];
+            let _: () = msg_send![frame_layer, setMasksToBounds: YES];
+        }
+    }
+
+    // Remove any visual effect views
+    if let Some(visual_effect_view) = get_visual_effect_view(ns_window) {
+        let _: () = msg_send![visual_effect_view, removeFromSuperview];
+    }
+
+    // Force window to update
+    let _: () = msg_send![ns_window, display];
+    let _: () = msg_send![ns_window, invalidateShadow];
+    let _: () = msg_send![content_view, setNeedsDisplay: YES];
+}
+
+unsafe fn get_visual_effect_view(window: id) -> Option<id> {
+    let content_view: id = msg_send![window, contentView];
+    let subviews: id = msg_send![content_view, subviews];
+    let count: usize = msg_send![subviews, count];
+
+    for i in 0..count {
+        let view: id = msg_send![subviews, objectAtIndex: i];
+        let class_name: id = msg_send![view, className];
+        let class_name_str = std::ffi::CStr::from_ptr(msg_send![class_name, UTF8String]);
+        if let Ok(name) = class_name_str.to_str() {
+            if name.contains("NSVisualEffectView") {
+                return Some(view);
+            }
+        }
+    }
+    None
+}
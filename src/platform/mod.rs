@@ -0,0 +1,37 @@
+use muda::Menu;
+use tao::window::Window;
+use wry::{WebView, WebViewBuilder};
+
+use crate::config::WindowConfig;
+
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(target_os = "macos")]
+mod macos;
+
+#[cfg(target_os = "linux")]
+pub use linux::Gtk as Native;
+#[cfg(target_os = "macos")]
+pub use macos::MacOs as Native;
+
+// Window chrome and app-level integration that tao doesn't cover the same way everywhere
+pub trait Platform {
+    // Brings the app to the foreground as a regular, dock-visible application
+    fn activate(&self);
+    // The parts of a WindowConfig that WindowConfig::builder can't express
+    fn style_window(&self, window: &Window, config: &WindowConfig);
+    fn set_app_name(&self, title: &str);
+    // Attaches the menu bar, to the app on macOS and to the window elsewhere
+    fn set_menu(&self, menu: &Menu, window: &Window) -> muda::Result<()>;
+    fn remove_menu(&self, menu: &Menu, window: &Window) -> muda::Result<()>;
+    // wry attaches to a native window on macOS but needs a GTK container on Linux
+    fn build_webview<'a>(
+        &self,
+        builder: WebViewBuilder<'a>,
+        window: &'a Window,
+    ) -> wry::Result<WebView>;
+}
+
+pub fn native() -> Native {
+    Native
+}
@@ -0,0 +1,346 @@
+use std::{borrow::Cow, fs, path::Path, sync::Arc};
+
+use wry::http::{header, response::Builder, Request, Response};
+
+use crate::{
+    assets::{content_type, AssetManager, HTML_ENTRY},
+    compression,
+    http_cache::{self, Validators},
+    range::{self, RangeError},
+};
+
+pub type ProtocolResponse = Response<Cow<'static, [u8]>>;
+
+// Custom protocol URLs put the first path segment in the host, e.g. assets://assets/index.js
+pub fn request_path(uri: &wry::http::Uri) -> String {
+    format!("{}{}", uri.host().unwrap_or(""), uri.path())
+}
+
+// The client-side route for an application URL, e.g. "settings/profile"
+fn app_route(uri: &wry::http::Uri) -> String {
+    let route = request_path(uri);
+    let route = route.trim_matches('/');
+
+    // The app is loaded from application://index.html, so absolute URLs the page
+    // builds itself (like /vite.svg) land under the index.html host
+    match route.strip_prefix(HTML_ENTRY) {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+            rest.trim_start_matches('/').to_string()
+        }
+        _ => route.to_string(),
+    }
+}
+
+// History-API fallback: real files are served, extensionless routes get the app
+// shell, and anything else that looks like a file is a genuine 404
+pub fn serve_application(
+    asset_manager: &AssetManager,
+    nonce: &str,
+    req: &Request<Vec<u8>>,
+) -> ProtocolResponse {
+    let route = app_route(req.uri());
+    if route.is_empty() || route == HTML_ENTRY {
+        return serve_html(asset_manager, nonce, req);
+    }
+
+    if asset_manager
+        .resolve(&route)
+        .is_ok_and(|path| path.is_file())
+    {
+        return serve_file(asset_manager, req, &route);
+    }
+
+    if Path::new(&route).extension().is_some() {
+        eprintln!("Failed to load application file {}: not found", route);
+        return empty_response(404);
+    }
+
+    serve_html(asset_manager, nonce, req)
+}
+
+fn serve_html(
+    asset_manager: &AssetManager,
+    nonce: &str,
+    req: &Request<Vec<u8>>,
+) -> ProtocolResponse {
+    // The nonce changes every launch, so it is part of the page's identity
+    let mut validators = match asset_manager.metadata(HTML_ENTRY) {
+        Ok(metadata) => Validators::from_metadata(&metadata, None).varied_by(nonce),
+        Err(e) => {
+            eprintln!("Failed to load HTML: {}", e);
+            return empty_response(500);
+        }
+    };
+    // A rebuild changes the tags injected from the manifest without touching index.html
+    if let Some(modified) = asset_manager.manifest_modified() {
+        validators = validators.varied_by_file(modified);
+    }
+
+    if validators.is_not_modified(req.headers()) {
+        return with_cache_headers(Response::builder(), &validators, http_cache::REVALIDATE)
+            .status(304)
+            .body(Cow::Owned(Vec::new()))
+            .unwrap();
+    }
+
+    match asset_manager.get_html(nonce) {
+        Ok(content) => with_cache_headers(
+            Response::builder().header("Content-Type", "text/html"),
+            &validators,
+            http_cache::REVALIDATE,
+        )
+        .body(Cow::Owned(content))
+        .unwrap(),
+        Err(e) => {
+            eprintln!("Failed to load HTML: {}", e);
+            empty_response(500)
+        }
+    }
+}
+
+pub fn serve_assets(asset_manager: &AssetManager, req: &Request<Vec<u8>>) -> ProtocolResponse {
+    serve_file(asset_manager, req, &request_path(req.uri()))
+}
+
+fn serve_file(
+    asset_manager: &AssetManager,
+    req: &Request<Vec<u8>>,
+    path: &str,
+) -> ProtocolResponse {
+    let content_type = content_type(path);
+
+    if let Some(range) = http_cache::header_str(req.headers(), header::RANGE) {
+        if let Some(response) = serve_range(asset_manager, req, path, content_type, range) {
+            return response;
+        }
+    }
+
+    let accepted = compression::accepted_encodings(http_cache::header_str(
+        req.headers(),
+        header::ACCEPT_ENCODING,
+    ));
+
+    let cache_control = http_cache::cache_control(path);
+
+    // Revalidation is answered from metadata, before anything is read or compressed
+    let (metadata, encoding) = match asset_manager.negotiate_encoding(path, content_type, &accepted)
+    {
+        Ok(negotiated) => negotiated,
+        Err(e) => {
+            eprintln!("Failed to load asset {}: {}", path, e);
+            return empty_response(404);
+        }
+    };
+    let validators = Validators::from_metadata(&metadata, encoding);
+    if validators.is_not_modified(req.headers()) {
+        return with_cache_headers(asset_headers(), &validators, cache_control)
+            .status(304)
+            .body(Cow::Owned(Vec::new()))
+            .unwrap();
+    }
+
+    let asset = match asset_manager.load_encoded_asset(path, content_type, &accepted) {
+        Ok(asset) => asset,
+        Err(e) => {
+            eprintln!("Failed to load asset {}: {}", path, e);
+            return empty_response(404);
+        }
+    };
+
+    // Compression can still fall back to the identity encoding
+    let validators = Validators::from_metadata(&asset.metadata, asset.encoding);
+    let mut response = with_cache_headers(asset_headers(), &validators, cache_control);
+
+    response = response
+        .header("Content-Type", content_type)
+        .header("Accept-Ranges", "bytes");
+    if let Some(encoding) = asset.encoding {
+        response = response.header("Content-Encoding", encoding.header_value());
+    }
+
+    response
+        .body(Cow::Owned(Arc::unwrap_or_clone(asset.data)))
+        .unwrap()
+}
+
+// Answers a Range request straight from disk, or returns None to serve the full body instead
+fn serve_range(
+    asset_manager: &AssetManager,
+    req: &Request<Vec<u8>>,
+    path: &str,
+    content_type: &str,
+    range_header: &str,
+) -> Option<ProtocolResponse> {
+    let metadata = asset_manager
+        .resolve(path)
+        .and_then(|file_path| fs::metadata(&file_path).map(|metadata| (file_path, metadata)));
+    let (file_path, metadata) = match metadata {
+        Ok(found) => found,
+        Err(e) => {
+            eprintln!("Failed to load asset {}: {}", path, e);
+            return Some(empty_response(404));
+        }
+    };
+
+    // Ranges always address the identity encoding
+    let validators = Validators::from_metadata(&metadata, None);
+    let response = with_cache_headers(
+        asset_headers(),
+        &validators,
+        http_cache::cache_control(path),
+    );
+
+    if validators.is_not_modified(req.headers()) {
+        return Some(response.status(304).body(Cow::Owned(Vec::new())).unwrap());
+    }
+    if !http_cache::if_range_matches(&validators, req.headers()) {
+        return None;
+    }
+
+    let total = metadata.len();
+    let range = match range::parse(range_header, total) {
+        Ok(range) => range::limit(range),
+        Err(RangeError::Unsupported) => return None,
+        Err(RangeError::Unsatisfiable) => {
+            return Some(
+                response
+                    .status(416)
+                    .header("Content-Range", format!("bytes */{}", total))
+                    .body(Cow::Owned(Vec::new()))
+                    .unwrap(),
+            )
+        }
+    };
+
+    match range::read(&file_path, range) {
+        Ok(data) => Some(
+            response
+                .status(206)
+                .header("Content-Type", content_type)
+                .header("Accept-Ranges", "bytes")
+                .header("Content-Range", range.content_range(total))
+                .header("Content-Length", data.len())
+                .body(Cow::Owned(data))
+                .unwrap(),
+        ),
+        Err(e) => {
+            eprintln!("Failed to read range of asset {}: {}", path, e);
+            Some(empty_response(500))
+        }
+    }
+}
+
+// Security headers are added on top of these by SecurityPolicy::apply
+fn asset_headers() -> Builder {
+    Response::builder().header("Vary", "Accept-Encoding")
+}
+
+fn with_cache_headers(
+    mut builder: Builder,
+    validators: &Validators,
+    cache_control: &str,
+) -> Builder {
+    builder = builder
+        .header("ETag", &validators.etag)
+        .header("Cache-Control", cache_control);
+    if let Some(last_modified) = validators.last_modified_header() {
+        builder = builder.header("Last-Modified", last_modified);
+    }
+    builder
+}
+
+fn empty_response(status: u16) -> ProtocolResponse {
+    Response::builder()
+        .status(status)
+        .body(Cow::Owned(Vec::new()))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    struct Dist(AssetManager);
+
+    impl Dist {
+        fn new(name: &str) -> Self {
+            let path = env::temp_dir().join(format!("rust-gui-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(path.join("assets")).unwrap();
+            fs::write(
+                path.join(HTML_ENTRY),
+                "<html><head></head><body>shell</body></html>",
+            )
+            .unwrap();
+            fs::write(path.join("assets/app.js"), "console.log('app')").unwrap();
+            Self(AssetManager::with_base_path(path))
+        }
+
+        fn get(&self, uri: &str) -> ProtocolResponse {
+            let req = Request::builder().uri(uri).body(Vec::new()).unwrap();
+            serve_application(&self.0, "nonce", &req)
+        }
+    }
+
+    impl Drop for Dist {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(self.0.base_path());
+        }
+    }
+
+    fn body(response: &ProtocolResponse) -> String {
+        String::from_utf8_lossy(response.body()).into_owned()
+    }
+
+    fn route(uri: &str) -> String {
+        app_route(&uri.parse().unwrap())
+    }
+
+    #[test]
+    fn routes_are_relative_to_the_index_html_host() {
+        assert_eq!(route("application://index.html"), "");
+        assert_eq!(route("application://index.html/"), "");
+        assert_eq!(
+            route("application://index.html/settings/profile"),
+            "settings/profile"
+        );
+        assert_eq!(route("application://index.html/settings?tab=2"), "settings");
+        assert_eq!(route("application://assets/app.js"), "assets/app.js");
+    }
+
+    #[test]
+    fn deep_links_get_the_app_shell() {
+        let dist = Dist::new("deep-link");
+        let response = dist.get("application://index.html/settings/profile");
+        assert_eq!(response.status(), 200);
+        assert!(body(&response).contains("shell"));
+    }
+
+    #[test]
+    fn existing_files_are_served() {
+        let dist = Dist::new("existing-file");
+        let response = dist.get("application://index.html/assets/app.js");
+        assert_eq!(response.status(), 200);
+        assert_eq!(body(&response), "console.log('app')");
+    }
+
+    #[test]
+    fn missing_files_are_not_found() {
+        let dist = Dist::new("missing-file");
+        let response = dist.get("application://index.html/assets/missing.js");
+        assert_eq!(response.status(), 404);
+    }
+
+    #[test]
+    fn query_strings_are_ignored() {
+        let dist = Dist::new("query-string");
+        let response = dist.get("application://index.html/settings?tab=2");
+        assert!(body(&response).contains("shell"));
+        let response = dist.get("application://index.html/assets/app.js?v=2");
+        assert_eq!(body(&response), "console.log('app')");
+        let response = dist.get("application://index.html/assets/missing.js?v=2");
+        assert_eq!(response.status(), 404);
+    }
+}
@@ -0,0 +1,171 @@
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+// Every range is answered with at most this many bytes, so seeking a long video never reads
+// it whole. The Content-Range header tells the client where the answer stops.
+pub const MAX_RANGE_CHUNK: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    pub start: u64,
+    // Inclusive, like the Content-Range header
+    pub end: u64,
+}
+
+impl ByteRange {
+    pub fn length(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    pub fn content_range(&self, total: u64) -> String {
+        format!("bytes {}-{}/{}", self.start, self.end, total)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RangeError {
+    // Malformed or multi-range headers are ignored and the full body is served
+    Unsupported,
+    // Well-formed but outside the file, answered with 416
+    Unsatisfiable,
+}
+
+// Parses a single `bytes=` range against a resource of `total` bytes
+pub fn parse(header: &str, total: u64) -> Result<ByteRange, RangeError> {
+    let spec = header
+        .trim()
+        .strip_prefix("bytes=")
+        .ok_or(RangeError::Unsupported)?;
+    if spec.contains(',') {
+        return Err(RangeError::Unsupported);
+    }
+    let (start, end) = spec.split_once('-').ok_or(RangeError::Unsupported)?;
+    let (start, end) = (start.trim(), end.trim());
+
+    let parse_pos = |s: &str| s.parse::<u64>().map_err(|_| RangeError::Unsupported);
+
+    let range = if start.is_empty() {
+        // Suffix range: the last N bytes
+        let suffix = parse_pos(end)?;
+        if suffix == 0 || total == 0 {
+            return Err(RangeError::Unsatisfiable);
+        }
+        ByteRange {
+            start: total.saturating_sub(suffix),
+            end: total - 1,
+        }
+    } else {
+        let start = parse_pos(start)?;
+        let end = if end.is_empty() {
+            total.saturating_sub(1)
+        } else {
+            let end = parse_pos(end)?;
+            if end < start {
+                return Err(RangeError::Unsupported);
+            }
+            end.min(total.saturating_sub(1))
+        };
+        if start >= total {
+            return Err(RangeError::Unsatisfiable);
+        }
+        ByteRange { start, end }
+    };
+
+    Ok(range)
+}
+
+// Caps a range, open-ended or closed, to MAX_RANGE_CHUNK bytes; the client asks for the rest
+// as it needs it
+pub fn limit(range: ByteRange) -> ByteRange {
+    ByteRange {
+        start: range.start,
+        end: range.end.min(range.start + MAX_RANGE_CHUNK - 1),
+    }
+}
+
+pub fn read(path: &Path, range: ByteRange) -> Result<Vec<u8>, io::Error> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(range.start))?;
+
+    let mut data = Vec::with_capacity(range.length() as usize);
+    file.take(range.length()).read_to_end(&mut data)?;
+    if (data.len() as u64) < range.length() {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("{} changed while reading", path.display()),
+        ));
+    }
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start: u64, end: u64) -> Result<ByteRange, RangeError> {
+        Ok(ByteRange { start, end })
+    }
+
+    #[test]
+    fn parses_closed_and_open_ranges() {
+        assert_eq!(parse("bytes=0-99", 1000), range(0, 99));
+        assert_eq!(parse(" bytes= 100 - 199 ", 1000), range(100, 199));
+        assert_eq!(parse("bytes=5-", 10), range(5, 9));
+        // An end past the file is cut to the last byte
+        assert_eq!(parse("bytes=990-5000", 1000), range(990, 999));
+    }
+
+    #[test]
+    fn parses_suffix_ranges() {
+        assert_eq!(parse("bytes=-100", 1000), range(900, 999));
+        assert_eq!(parse("bytes=-5000", 1000), range(0, 999));
+        assert_eq!(parse("bytes=-0", 1000), Err(RangeError::Unsatisfiable));
+    }
+
+    #[test]
+    fn start_past_the_end_is_unsatisfiable() {
+        assert_eq!(parse("bytes=5-", 3), Err(RangeError::Unsatisfiable));
+        assert_eq!(parse("bytes=3-3", 3), Err(RangeError::Unsatisfiable));
+    }
+
+    #[test]
+    fn zero_length_file_is_unsatisfiable() {
+        assert_eq!(parse("bytes=0-", 0), Err(RangeError::Unsatisfiable));
+        assert_eq!(parse("bytes=0-0", 0), Err(RangeError::Unsatisfiable));
+        assert_eq!(parse("bytes=-1", 0), Err(RangeError::Unsatisfiable));
+    }
+
+    #[test]
+    fn ignores_multi_range_and_malformed_headers() {
+        assert_eq!(parse("bytes=0-1,5-6", 10), Err(RangeError::Unsupported));
+        assert_eq!(parse("items=0-1", 10), Err(RangeError::Unsupported));
+        assert_eq!(parse("bytes=5-2", 10), Err(RangeError::Unsupported));
+        assert_eq!(parse("bytes=a-b", 10), Err(RangeError::Unsupported));
+        assert_eq!(parse("bytes=5", 10), Err(RangeError::Unsupported));
+    }
+
+    #[test]
+    fn limits_ranges_to_one_chunk() {
+        let total = 10 * MAX_RANGE_CHUNK;
+        let open = parse("bytes=10-", total).unwrap();
+        assert_eq!(
+            limit(open),
+            ByteRange {
+                start: 10,
+                end: 10 + MAX_RANGE_CHUNK - 1
+            }
+        );
+        // Closed ranges are capped the same way
+        let closed = parse(&format!("bytes=0-{}", 3 * MAX_RANGE_CHUNK), total).unwrap();
+        assert_eq!(limit(closed).length(), MAX_RANGE_CHUNK);
+        assert_eq!(limit(ByteRange { start: 0, end: 9 }).length(), 10);
+        assert_eq!(
+            ByteRange { start: 0, end: 9 }.content_range(100),
+            "bytes 0-9/100"
+        );
+    }
+}
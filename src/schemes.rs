@@ -0,0 +1,260 @@
+use std::{
+    borrow::Cow,
+    error::Error,
+    fmt, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crossbeam_channel::Sender;
+use wry::{
+    http::{Request, Response, StatusCode},
+    WebViewBuilder,
+};
+
+use crate::{
+    assets::AssetManager,
+    overlay::ErrorReport,
+    protocol::{self, ProtocolResponse},
+    security::SecurityPolicy,
+};
+
+// Owned by the webview. The app's own schemes are registered first, so taking one of those
+// names is a duplicate.
+const RESERVED_SCHEMES: [&str; 8] = [
+    "about", "blob", "data", "file", "http", "https", "ws", "wss",
+];
+
+pub type SchemeResult = Result<ProtocolResponse, Box<dyn Error + Send + Sync>>;
+type SchemeHandler = Arc<dyn Fn(&Request<Vec<u8>>) -> SchemeResult + Send + Sync>;
+
+#[derive(Debug)]
+pub enum SchemeError {
+    InvalidName(String),
+    Reserved(String),
+    Duplicate(String),
+}
+
+impl fmt::Display for SchemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidName(name) => write!(f, "Invalid URI scheme name: {}", name),
+            Self::Reserved(name) => write!(f, "URI scheme is reserved: {}", name),
+            Self::Duplicate(name) => write!(f, "URI scheme already registered: {}", name),
+        }
+    }
+}
+
+impl Error for SchemeError {}
+
+// Custom URI schemes the webview serves from Rust, built-in ones included
+#[derive(Default)]
+pub struct SchemeRegistry {
+    schemes: Vec<(String, SchemeHandler)>,
+    errors: Option<Sender<ErrorReport>>,
+}
+
+impl SchemeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Handlers see the full request; errors are logged and answered with an empty 404/500
+    pub fn register<F>(&mut self, name: &str, handler: F) -> Result<&mut Self, SchemeError>
+    where
+        F: Fn(&Request<Vec<u8>>) -> SchemeResult + Send + Sync + 'static,
+    {
+        let valid = name.starts_with(|c: char| c.is_ascii_lowercase())
+            && name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "+-.".contains(c));
+        if !valid {
+            return Err(SchemeError::InvalidName(name.to_string()));
+        }
+        if RESERVED_SCHEMES.contains(&name) {
+            return Err(SchemeError::Reserved(name.to_string()));
+        }
+        if self.schemes.iter().any(|(existing, _)| existing == name) {
+            return Err(SchemeError::Duplicate(name.to_string()));
+        }
+
+        self.schemes.push((name.to_string(), Arc::new(handler)));
+        Ok(self)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.schemes.iter().map(|(name, _)| name.as_str())
+    }
+
+    // Failed requests are also sent here, for the error overlay, except a missing image or
+    // favicon
+    pub fn report_errors(&mut self, errors: Sender<ErrorReport>) -> &mut Self {
+        self.errors = Some(errors);
+        self
+    }
+
+    pub fn attach<'a>(
+        &self,
+        mut builder: WebViewBuilder<'a>,
+        security: &Arc<SecurityPolicy>,
+    ) -> WebViewBuilder<'a> {
+        for (name, handler) in &self.schemes {
+            let name = name.clone();
+            let handler = handler.clone();
+            let security = security.clone();
+            let errors = self.errors.clone();
+
+            builder = builder.with_custom_protocol(name.clone(), move |_, req| {
+                let url = format!("{}://{}", name, protocol::request_path(req.uri()));
+                let (response, message) = match handler(&req) {
+                    Ok(response) => {
+                        let message = format!("Request failed with status {}", response.status());
+                        (response, message)
+                    }
+                    Err(e) => {
+                        eprintln!("Failed to serve {}: {}", url, e);
+                        (error_response(e.as_ref()), e.to_string())
+                    }
+                };
+
+                if let Some(errors) = &errors {
+                    if should_report(&req, response.status()) {
+                        let _ = errors.send(ErrorReport {
+                            file: Some(url),
+                            ..ErrorReport::new("Asset", message)
+                        });
+                    }
+                }
+                security.apply(&req, response)
+            });
+        }
+        builder
+    }
+}
+
+// Serves a directory with the caching, compression and range support assets:// has
+pub fn directory(
+    root: impl Into<PathBuf>,
+) -> impl Fn(&Request<Vec<u8>>) -> SchemeResult + Send + Sync + 'static {
+    let asset_manager = AssetManager::with_base_path(root.into());
+    move |req| Ok(protocol::serve_assets(&asset_manager, req))
+}
+
+// Scripts, stylesheets and pages that fail break the app; a missing image or favicon is left
+// to the page
+fn should_report(req: &Request<Vec<u8>>, status: StatusCode) -> bool {
+    status.is_server_error()
+        || (status.is_client_error() && !is_image(&protocol::request_path(req.uri())))
+}
+
+fn is_image(path: &str) -> bool {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase);
+    matches!(
+        extension.as_deref(),
+        Some("png" | "jpg" | "jpeg" | "gif" | "webp" | "avif" | "svg" | "bmp" | "ico")
+    )
+}
+
+fn error_response(error: &(dyn Error + Send + Sync + 'static)) -> ProtocolResponse {
+    let status = match error.downcast_ref::<io::Error>().map(io::Error::kind) {
+        Some(io::ErrorKind::NotFound) => 404,
+        Some(io::ErrorKind::PermissionDenied) => 403,
+        _ => 500,
+    };
+
+    Response::builder()
+        .status(status)
+        .body(Cow::Owned(Vec::new()))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn not_found(_: &Request<Vec<u8>>) -> SchemeResult {
+        Err(Box::new(io::Error::from(io::ErrorKind::NotFound)))
+    }
+
+    fn request(uri: &str) -> Request<Vec<u8>> {
+        Request::builder().uri(uri).body(Vec::new()).unwrap()
+    }
+
+    #[test]
+    fn reports_failed_scripts_styles_and_pages() {
+        for uri in [
+            "assets://assets/index-4f3a.js",
+            "assets://assets/worker.mjs",
+            "assets://assets/index-4f3a.css",
+            "application://index.html/settings",
+            "application://index.html/about.html",
+        ] {
+            assert!(
+                should_report(&request(uri), StatusCode::NOT_FOUND),
+                "{}",
+                uri
+            );
+            assert!(
+                should_report(&request(uri), StatusCode::FORBIDDEN),
+                "{}",
+                uri
+            );
+        }
+    }
+
+    #[test]
+    fn leaves_missing_images_to_the_page() {
+        for uri in [
+            "application://index.html/favicon.ico",
+            "application://favicon.ico",
+            "assets://assets/logo.svg",
+            "assets://assets/photo.JPG",
+        ] {
+            assert!(
+                !should_report(&request(uri), StatusCode::NOT_FOUND),
+                "{}",
+                uri
+            );
+            // A server error is a bug, whatever was asked for
+            assert!(
+                should_report(&request(uri), StatusCode::INTERNAL_SERVER_ERROR),
+                "{}",
+                uri
+            );
+        }
+    }
+
+    #[test]
+    fn ignores_successful_responses() {
+        let script = request("assets://assets/index-4f3a.js");
+        assert!(!should_report(&script, StatusCode::OK));
+        assert!(!should_report(&script, StatusCode::NOT_MODIFIED));
+        assert!(!should_report(&script, StatusCode::PARTIAL_CONTENT));
+    }
+
+    #[test]
+    fn validates_scheme_names() {
+        let mut schemes = SchemeRegistry::new();
+        assert!(schemes.register("thumbs", not_found).is_ok());
+        assert!(schemes.register("my-app.v2", not_found).is_ok());
+        assert!(matches!(
+            schemes.register("Thumbs", not_found),
+            Err(SchemeError::InvalidName(_))
+        ));
+        assert!(matches!(
+            schemes.register("2d", not_found),
+            Err(SchemeError::InvalidName(_))
+        ));
+        assert!(matches!(
+            schemes.register("https", not_found),
+            Err(SchemeError::Reserved(_))
+        ));
+        assert!(matches!(
+            schemes.register("thumbs", not_found),
+            Err(SchemeError::Duplicate(_))
+        ));
+    }
+}
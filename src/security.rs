@@ -0,0 +1,445 @@
+use std::env;
+
+use wry::http::{
+    header::{self, HeaderValue},
+    Request,
+};
+
+use crate::protocol::ProtocolResponse;
+
+// Pages served by the application protocol have origins like application://index.html, or
+// application://settings for deep links; see wry's with_custom_protocol docs
+pub const APP_SCHEME: &str = "application://";
+
+// Placeholder in CSP sources that is replaced with the per-launch nonce
+pub const NONCE: &str = "'nonce-{nonce}'";
+
+// Where registered schemes are allowed as sources. Scripts and styles stay limited to the
+// app's assets and the nonce.
+const SCHEME_DIRECTIVES: [&str; 5] = [
+    "default-src",
+    "img-src",
+    "media-src",
+    "font-src",
+    "connect-src",
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct ContentSecurityPolicy {
+    directives: Vec<(String, Vec<String>)>,
+}
+
+impl ContentSecurityPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Sets a directive, replacing any sources it already had
+    pub fn directive(mut self, name: &str, sources: &[&str]) -> Self {
+        let sources = sources.iter().map(|s| s.to_string()).collect();
+        match self.directives.iter_mut().find(|(n, _)| n == name) {
+            Some((_, existing)) => *existing = sources,
+            None => self.directives.push((name.to_string(), sources)),
+        }
+        self
+    }
+
+    // Adds a source to a directive that is already set, unless it is listed
+    pub fn add_source(&mut self, name: &str, source: &str) {
+        if let Some((_, sources)) = self.directives.iter_mut().find(|(n, _)| n == name) {
+            if !sources.iter().any(|existing| existing == source) {
+                sources.push(source.to_string());
+            }
+        }
+    }
+
+    pub fn header_value(&self, nonce: &str) -> String {
+        self.directives
+            .iter()
+            .map(|(name, sources)| {
+                let mut directive = name.clone();
+                for source in sources {
+                    directive.push(' ');
+                    directive.push_str(&source.replace("{nonce}", nonce));
+                }
+                directive
+            })
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+// Which origins may read responses with CORS, e.g. crossorigin scripts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AllowOrigin {
+    Any,
+    // Echoes the request's origin when it is an application:// page
+    App,
+}
+
+// Every header the custom protocols send for security reasons, configured in one place
+#[derive(Debug, Clone)]
+pub struct SecurityPolicy {
+    nonce: String,
+    pub content_security_policy: Option<ContentSecurityPolicy>,
+    pub allow_origin: Option<AllowOrigin>,
+    pub opener_policy: Option<String>,
+    pub embedder_policy: Option<String>,
+    // Needed with require-corp, or plain <img> and <video> loads from the schemes are blocked
+    pub resource_policy: Option<String>,
+    pub referrer_policy: Option<String>,
+    pub nosniff: bool,
+}
+
+impl SecurityPolicy {
+    // Locked down for release builds: nonce-gated inline code and app-only origins
+    pub fn strict() -> Self {
+        let csp = ContentSecurityPolicy::new()
+            .directive("default-src", &["'self'", "application:", "assets:"])
+            .directive("script-src", &["'self'", "assets:", NONCE])
+            .directive("style-src", &["'self'", "assets:", NONCE])
+            .directive("img-src", &["'self'", "assets:", "data:", "blob:"])
+            .directive("media-src", &["'self'", "assets:", "blob:"])
+            .directive("font-src", &["'self'", "assets:", "data:"])
+            // The IPC WebSocket listens on an ephemeral localhost port
+            .directive("connect-src", &["'self'", "assets:", "ws://127.0.0.1:*"])
+            .directive("object-src", &["'none'"])
+            .directive("base-uri", &["'none'"])
+            .directive("frame-ancestors", &["'none'"]);
+
+        Self {
+            nonce: random_token(),
+            content_security_policy: Some(csp),
+            allow_origin: Some(AllowOrigin::App),
+            opener_policy: Some("same-origin".to_string()),
+            embedder_policy: Some("require-corp".to_string()),
+            resource_policy: Some("cross-origin".to_string()),
+            referrer_policy: Some("no-referrer".to_string()),
+            nosniff: true,
+        }
+    }
+
+    // What the assets protocol has always sent, loose enough for Vite tooling
+    pub fn development() -> Self {
+        Self {
+            nonce: random_token(),
+            content_security_policy: None,
+            allow_origin: Some(AllowOrigin::Any),
+            opener_policy: Some("same-origin".to_string()),
+            embedder_policy: Some("require-corp".to_string()),
+            resource_policy: Some("cross-origin".to_string()),
+            referrer_policy: None,
+            nosniff: false,
+        }
+    }
+
+    // Strict unless this is a debug build; RUST_GUI_SECURITY picks a profile explicitly
+    pub fn for_build() -> Self {
+        match env::var("RUST_GUI_SECURITY").as_deref() {
+            Ok("strict") => Self::strict(),
+            Ok("development") => Self::development(),
+            _ if cfg!(debug_assertions) => Self::development(),
+            _ => Self::strict(),
+        }
+    }
+
+    pub fn nonce(&self) -> &str {
+        &self.nonce
+    }
+
+    // Lets pages load from the given schemes, such as the ones on the SchemeRegistry
+    pub fn allow_schemes<'a>(&mut self, names: impl IntoIterator<Item = &'a str>) {
+        let Some(csp) = &mut self.content_security_policy else {
+            return;
+        };
+        for name in names {
+            let source = format!("{}:", name);
+            for directive in SCHEME_DIRECTIVES {
+                csp.add_source(directive, &source);
+            }
+        }
+    }
+
+    pub fn apply(
+        &self,
+        request: &Request<Vec<u8>>,
+        mut response: ProtocolResponse,
+    ) -> ProtocolResponse {
+        let mut headers = Vec::new();
+        if let Some(csp) = &self.content_security_policy {
+            headers.push(("Content-Security-Policy", csp.header_value(&self.nonce)));
+        }
+        let origin = match &self.allow_origin {
+            Some(AllowOrigin::Any) => Some("*".to_string()),
+            Some(AllowOrigin::App) => {
+                headers.push(("Vary", "Origin".to_string()));
+                request
+                    .headers()
+                    .get(header::ORIGIN)
+                    .and_then(|origin| origin.to_str().ok())
+                    .filter(|origin| origin.starts_with(APP_SCHEME))
+                    .map(str::to_string)
+            }
+            None => None,
+        };
+        if let Some(origin) = origin {
+            headers.push(("Access-Control-Allow-Origin", origin));
+            headers.push((
+                "Access-Control-Allow-Methods",
+                "GET, POST, OPTIONS".to_string(),
+            ));
+            headers.push(("Access-Control-Allow-Headers", "Content-Type".to_string()));
+        }
+        if let Some(policy) = &self.opener_policy {
+            headers.push(("Cross-Origin-Opener-Policy", policy.clone()));
+        }
+        if let Some(policy) = &self.embedder_policy {
+            headers.push(("Cross-Origin-Embedder-Policy", policy.clone()));
+        }
+        if let Some(policy) = &self.resource_policy {
+            headers.push(("Cross-Origin-Resource-Policy", policy.clone()));
+        }
+        if let Some(policy) = &self.referrer_policy {
+            headers.push(("Referrer-Policy", policy.clone()));
+        }
+        if self.nosniff {
+            headers.push(("X-Content-Type-Options", "nosniff".to_string()));
+        }
+
+        for (name, value) in headers {
+            match HeaderValue::from_str(&value) {
+                // Vary already lists Accept-Encoding on assets
+                Ok(value) if name == "Vary" => {
+                    response.headers_mut().append(name, value);
+                }
+                Ok(value) => {
+                    response.headers_mut().insert(name, value);
+                }
+                Err(e) => eprintln!("Invalid value for {} header: {}", name, e),
+            }
+        }
+        response
+    }
+}
+
+// Tags <script> and <style> elements so the CSP lets them run. Works on tags, not text, so
+// script bodies, comments and tags that already carry a nonce are left alone.
+pub fn add_nonces(html: &str, nonce: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(index) = rest.find('<') {
+        out.push_str(&rest[..index]);
+        rest = &rest[index..];
+
+        if rest.starts_with("<!--") {
+            let end = rest.find("-->").map_or(rest.len(), |end| end + 3);
+            out.push_str(&rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+        let Some(name) = ["script", "style"]
+            .into_iter()
+            .find(|name| opens_tag(rest, name))
+        else {
+            // Other tags are copied whole, so their attribute values are never rewritten
+            let is_tag = rest[1..].starts_with(|c: char| c.is_ascii_alphabetic() || c == '/');
+            let end = if is_tag { tag_end(rest) } else { 1 };
+            out.push_str(&rest[..end]);
+            rest = &rest[end..];
+            continue;
+        };
+
+        let (tag, body) = rest.split_at(tag_end(rest));
+        let (open, attributes) = tag.split_at(1 + name.len());
+        out.push_str(open);
+        if !has_attribute(attributes, "nonce") {
+            out.push_str(&format!(r#" nonce="{}""#, nonce));
+        }
+        out.push_str(attributes);
+
+        // The body is raw text up to the closing tag, whatever it contains
+        let body_end = body
+            .to_ascii_lowercase()
+            .find(&format!("</{}", name))
+            .unwrap_or(body.len());
+        out.push_str(&body[..body_end]);
+        rest = &body[body_end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+// `<name` followed by whitespace, `>` or `/`, in any case
+fn opens_tag(html: &str, name: &str) -> bool {
+    html.get(1..1 + name.len())
+        .is_some_and(|tag| tag.eq_ignore_ascii_case(name))
+        && html[1 + name.len()..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_whitespace() || c == '>' || c == '/')
+}
+
+// Length of the tag at the start of `html`, up to its `>` outside any quoted value
+fn tag_end(html: &str) -> usize {
+    let mut quote = None;
+    for (index, c) in html.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), c) if c == open => quote = None,
+            (None, '>') => return index + 1,
+            _ => (),
+        }
+    }
+    html.len()
+}
+
+fn has_attribute(attributes: &str, name: &str) -> bool {
+    let attributes = attributes.to_ascii_lowercase();
+    attributes.match_indices(name).any(|(index, _)| {
+        attributes[..index].ends_with(|c: char| c.is_ascii_whitespace())
+            && attributes[index + name.len()..]
+                .trim_start()
+                .starts_with(['=', '>', '/'])
+    })
+}
+
+// 128 random bits as hex, used for CSP nonces and other per-launch secrets
+pub fn random_token() -> String {
+    rand::random::<[u8; 16]>()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::borrow::Cow;
+
+    use wry::http::Response;
+
+    fn apply(policy: &SecurityPolicy, origin: Option<&str>) -> ProtocolResponse {
+        let mut request = Request::builder().uri("assets://assets/index.js");
+        if let Some(origin) = origin {
+            request = request.header("Origin", origin);
+        }
+        let response = Response::builder()
+            .header("Vary", "Accept-Encoding")
+            .body(Cow::Owned(Vec::new()))
+            .unwrap();
+        policy.apply(&request.body(Vec::new()).unwrap(), response)
+    }
+
+    fn header<'a>(response: &'a ProtocolResponse, name: &str) -> Option<&'a str> {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    }
+
+    #[test]
+    fn strict_policy_echoes_application_origins() {
+        let policy = SecurityPolicy::strict();
+        for origin in ["application://index.html", "application://settings"] {
+            let response = apply(&policy, Some(origin));
+            assert_eq!(
+                header(&response, "Access-Control-Allow-Origin"),
+                Some(origin)
+            );
+        }
+
+        for origin in [Some("https://example.com"), None] {
+            let response = apply(&policy, origin);
+            assert_eq!(header(&response, "Access-Control-Allow-Origin"), None);
+        }
+
+        let response = apply(&policy, None);
+        let vary: Vec<_> = response.headers().get_all("Vary").iter().collect();
+        assert_eq!(vary, ["Accept-Encoding", "Origin"]);
+    }
+
+    #[test]
+    fn strict_policy_allows_registered_schemes() {
+        let mut policy = SecurityPolicy::strict();
+        policy.allow_schemes(["application", "assets", "thumbs"]);
+        let csp = policy
+            .content_security_policy
+            .as_ref()
+            .unwrap()
+            .header_value("n");
+        let directive = |name: &str| {
+            csp.split("; ")
+                .find(|directive| directive.starts_with(&format!("{} ", name)))
+                .unwrap()
+                .to_string()
+        };
+
+        assert_eq!(
+            directive("default-src"),
+            "default-src 'self' application: assets: thumbs:"
+        );
+        assert_eq!(
+            directive("img-src"),
+            "img-src 'self' assets: data: blob: application: thumbs:"
+        );
+        assert!(directive("connect-src").ends_with(" thumbs:"));
+        assert_eq!(
+            directive("script-src"),
+            "script-src 'self' assets: 'nonce-n'"
+        );
+    }
+
+    #[test]
+    fn development_policy_has_no_csp_to_extend() {
+        let mut policy = SecurityPolicy::development();
+        policy.allow_schemes(["thumbs"]);
+        assert!(policy.content_security_policy.is_none());
+    }
+
+    #[test]
+    fn resources_can_be_embedded_under_require_corp() {
+        for policy in [SecurityPolicy::strict(), SecurityPolicy::development()] {
+            let response = apply(&policy, None);
+            assert_eq!(
+                header(&response, "Cross-Origin-Embedder-Policy"),
+                Some("require-corp")
+            );
+            assert_eq!(
+                header(&response, "Cross-Origin-Resource-Policy"),
+                Some("cross-origin")
+            );
+        }
+    }
+
+    #[test]
+    fn adds_nonces_to_script_and_style_tags() {
+        let html = r#"<head><SCRIPT type=module src="/a.js"></SCRIPT><style>p{}</style><script>1</script></head>"#;
+        assert_eq!(
+            add_nonces(html, "n"),
+            r#"<head><SCRIPT nonce="n" type=module src="/a.js"></SCRIPT><style nonce="n">p{}</style><script nonce="n">1</script></head>"#
+        );
+    }
+
+    #[test]
+    fn leaves_script_bodies_and_comments_alone() {
+        let html =
+            r#"<script>const tag = "<script>";</script><!-- <style> --><p title="<script>">"#;
+        assert_eq!(
+            add_nonces(html, "n"),
+            r#"<script nonce="n">const tag = "<script>";</script><!-- <style> --><p title="<script>">"#
+        );
+    }
+
+    #[test]
+    fn skips_other_tags_and_existing_nonces() {
+        let html = r#"<scripts></scripts><stylesheet-x><script nonce="old" src="/a.js"></script>"#;
+        assert_eq!(add_nonces(html, "n"), html);
+
+        let quoted = r#"<script data-x="a>b">x</script>"#;
+        assert_eq!(
+            add_nonces(quoted, "n"),
+            r#"<script nonce="n" data-x="a>b">x</script>"#
+        );
+    }
+}
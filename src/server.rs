@@ -0,0 +1,580 @@
+use std::{
+    env, fs,
+    io::{self, Write},
+    net::{SocketAddr, TcpListener},
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Instant,
+};
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    handler::Handler,
+    http::{header, HeaderMap, Request, StatusCode, Uri},
+    middleware::{self, Next},
+    response::{Html, IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    runtime::Runtime,
+    sync::{broadcast, mpsc, oneshot, watch},
+    task::JoinHandle,
+};
+use tower_http::services::ServeDir;
+use url::Url;
+
+use crate::{
+    assets::AssetManager,
+    ipc::{self, ProtocolHandler},
+    security,
+};
+
+pub const DEFAULT_PORT: u16 = 3030;
+
+// Pushed events a slow client hasn't read yet; beyond this it starts dropping them
+const EVENT_BUFFER: usize = 4096;
+
+// Header carrying the IPC token on POST /ipc; WebSockets pass it as ?token=
+const TOKEN_HEADER: &str = "x-ipc-token";
+
+// window.ipcSocket: the same postMessage API as window.ipc, over a WebSocket.
+// Responses arrive as rust-response events, pushes from Rust as rust-event.
+const IPC_CLIENT: &str = r#"
+(() => {
+    if (window.ipcSocket) return;
+    const url = '{url}';
+    const pending = [];
+    let socket;
+    const connect = () => {
+        socket = new WebSocket(url);
+        socket.addEventListener('open', () => pending.splice(0).forEach((m) => socket.send(m)));
+        socket.addEventListener('message', (event) => {
+            const message = JSON.parse(event.data);
+            const name = 'event' in message ? 'rust-event' : 'rust-response';
+            window.dispatchEvent(new CustomEvent(name, { detail: message }));
+        });
+        socket.addEventListener('close', () => setTimeout(connect, 500));
+    };
+    connect();
+    window.ipcSocket = {
+        postMessage: (message) =>
+            socket.readyState === WebSocket.OPEN ? socket.send(message) : pending.push(message),
+    };
+})();
+"#;
+
+// Stands in for the webview's window.ipc so the app runs unchanged in a browser
+const IPC_BRIDGE: &str = "window.ipc = window.ipc || window.ipcSocket;";
+
+#[derive(Clone)]
+struct ServerState {
+    asset_manager: Arc<AssetManager>,
+    nonce: Arc<str>,
+}
+
+#[derive(Clone)]
+struct IpcState {
+    protocol_handler: Arc<dyn ProtocolHandler>,
+    token: Arc<str>,
+    events: broadcast::Sender<String>,
+    // Set once the app shuts down; every open WebSocket holds a receiver until it is done
+    closing: watch::Sender<bool>,
+}
+
+#[derive(Deserialize)]
+struct TokenQuery {
+    token: Option<String>,
+}
+
+// A message Rust pushes to the frontend unprompted
+#[derive(Serialize)]
+struct IpcEvent<'a, T> {
+    event: &'a str,
+    payload: &'a T,
+}
+
+// Pushes events to every connected IPC WebSocket
+#[derive(Clone)]
+pub struct Emitter {
+    events: broadcast::Sender<String>,
+}
+
+impl Emitter {
+    pub fn emit<T: Serialize>(&self, event: &str, payload: &T) {
+        match serde_json::to_string(&IpcEvent { event, payload }) {
+            // Nobody listening yet is not an error
+            Ok(json) => {
+                let _ = self.events.send(json);
+            }
+            Err(e) => eprintln!("Failed to serialize {} event: {}", event, e),
+        }
+    }
+}
+
+// The WebSocket IPC transport for the native window, on an ephemeral localhost port
+pub struct IpcServer {
+    // Owns the server task; dropping it shuts the transport down
+    runtime: Runtime,
+    server: JoinHandle<()>,
+    stop: oneshot::Sender<()>,
+    addr: SocketAddr,
+    state: IpcState,
+}
+
+impl IpcServer {
+    pub fn start(protocol_handler: Arc<dyn ProtocolHandler>) -> Result<Self, io::Error> {
+        let state = IpcState::new(protocol_handler);
+
+        let listener = TcpListener::bind(("127.0.0.1", 0))?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+
+        let runtime = Runtime::new()?;
+        let server = {
+            let _guard = runtime.enter();
+            axum::Server::from_tcp(listener).map_err(io::Error::other)?
+        };
+        let app = local_only(ipc_router(state.clone()), addr.port());
+        let (stop, stopped) = oneshot::channel();
+        let server = runtime.spawn(async move {
+            let server = server
+                .serve(app.into_make_service())
+                .with_graceful_shutdown(async {
+                    let _ = stopped.await;
+                });
+            if let Err(e) = server.await {
+                eprintln!("IPC server failed: {}", e);
+            }
+        });
+
+        println!("IPC WebSocket listening on ws://{}/ipc/ws", addr);
+        Ok(Self {
+            runtime,
+            server,
+            stop,
+            addr,
+            state,
+        })
+    }
+
+    pub fn url(&self) -> String {
+        format!("ws://{}/ipc/ws?token={}", self.addr, self.state.token)
+    }
+
+    // Initialization script that defines window.ipcSocket. The webview runs it in every
+    // document it loads, so only application:// pages and the dev server get the token.
+    pub fn client_script(&self, start_url: &str) -> String {
+        app_pages_only(&IPC_CLIENT.replace("{url}", &self.url()), start_url)
+    }
+
+    pub fn emitter(&self) -> Emitter {
+        self.state.emitter()
+    }
+
+    // Stops taking connections and requests. Calls already in flight are answered until the
+    // deadline, and whatever is still running then is cancelled along with the runtime.
+    pub fn shutdown(self, deadline: Instant) {
+        let _ = self.stop.send(());
+        self.state.closing.send_replace(true);
+
+        let closing = self.state.closing;
+        let server = self.server;
+        let drained = self.runtime.block_on(async move {
+            let drain = async {
+                let _ = server.await;
+                closing.closed().await;
+            };
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            tokio::time::timeout(timeout, drain).await.is_ok()
+        });
+        if !drained {
+            eprintln!("Cancelling IPC calls still running at shutdown");
+        }
+        self.runtime
+            .shutdown_timeout(deadline.saturating_duration_since(Instant::now()));
+    }
+}
+
+impl IpcState {
+    fn new(protocol_handler: Arc<dyn ProtocolHandler>) -> Self {
+        Self {
+            protocol_handler,
+            token: security::random_token().into(),
+            events: broadcast::channel(EVENT_BUFFER).0,
+            closing: watch::Sender::new(false),
+        }
+    }
+
+    fn emitter(&self) -> Emitter {
+        Emitter {
+            events: self.events.clone(),
+        }
+    }
+
+    // Compared in constant time so the token can't be guessed byte by byte
+    fn is_authorized(&self, token: Option<&str>) -> bool {
+        let Some(token) = token else {
+            return false;
+        };
+        token.len() == self.token.len()
+            && token
+                .bytes()
+                .zip(self.token.bytes())
+                .fold(0, |diff, (a, b)| diff | (a ^ b))
+                == 0
+    }
+}
+
+pub struct ServeOptions {
+    pub port: u16,
+    // Where clients other than the served page read the IPC token from
+    pub token_file: Option<PathBuf>,
+}
+
+// `--serve [--port N] [--token-file PATH]` serves the app to a browser instead of opening
+// a window
+pub fn serve_mode() -> Option<ServeOptions> {
+    let args: Vec<String> = env::args().collect();
+    if !args.iter().any(|arg| arg == "--serve") {
+        return None;
+    }
+
+    let value = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|index| args.get(index + 1))
+    };
+    Some(ServeOptions {
+        port: value("--port")
+            .and_then(|port| port.parse().ok())
+            .unwrap_or(DEFAULT_PORT),
+        token_file: value("--token-file").map(PathBuf::from),
+    })
+}
+
+pub fn run(
+    options: ServeOptions,
+    protocol_handler: Arc<dyn ProtocolHandler>,
+) -> Result<(), io::Error> {
+    let port = options.port;
+    let asset_manager = AssetManager::new().map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            "Failed to initialize asset manager",
+        )
+    })?;
+    let state = ServerState {
+        asset_manager: Arc::new(asset_manager),
+        nonce: security::random_token().into(),
+    };
+    let ipc = IpcState::new(protocol_handler);
+    match &options.token_file {
+        Some(path) => {
+            write_token(path, &ipc.token)?;
+            println!("IPC token written to {}", path.display());
+        }
+        None => println!("IPC token: {}", ipc.token),
+    }
+
+    let runtime = Runtime::new()?;
+    runtime.block_on(async move {
+        let addr = SocketAddr::from(([127, 0, 0, 1], port));
+        let server = axum::Server::try_bind(&addr)
+            .map_err(|e| io::Error::new(io::ErrorKind::AddrInUse, e))?;
+
+        println!("Serving app and IPC on http://{}", addr);
+        server
+            .serve(local_only(router(state, ipc), port).into_make_service())
+            .await
+            .map_err(io::Error::other)
+    })
+}
+
+fn router(state: ServerState, ipc: IpcState) -> Router {
+    let files = ServeDir::new(state.asset_manager.base_path())
+        .precompressed_br()
+        .precompressed_gzip()
+        .fallback(spa_fallback.with_state((state.clone(), ipc.clone())));
+
+    Router::new()
+        .route("/", get(index))
+        .route("/index.html", get(index))
+        .fallback_service(files)
+        .with_state((state, ipc.clone()))
+        .layer(middleware::from_fn(hide_dotfiles))
+        .merge(ipc_router(ipc))
+}
+
+fn ipc_router(ipc: IpcState) -> Router {
+    Router::new()
+        .route("/ipc", post(ipc_http))
+        .route("/ipc/ws", get(ipc_ws))
+        .with_state(ipc)
+}
+
+async fn index(State((state, ipc)): State<(ServerState, IpcState)>) -> Response {
+    match state.asset_manager.get_html(&state.nonce) {
+        Ok(html) => {
+            // Asset URLs point at the webview's assets:// scheme, which is plain / here.
+            // The token is only readable by pages this server hands out.
+            let html = String::from_utf8_lossy(&html).replace("assets://", "/");
+            let client = IPC_CLIENT.replace(
+                "'{url}'",
+                &format!("`ws://${{location.host}}/ipc/ws?token={}`", ipc.token),
+            );
+            let bridge = format!(
+                r#"<script nonce="{}">{}{}</script>"#,
+                state.nonce, client, IPC_BRIDGE
+            );
+            let html = match head_end(&html) {
+                Some(index) => format!("{}{}{}", &html[..index], bridge, &html[index..]),
+                None => format!("{}{}", bridge, html),
+            };
+            Html(html).into_response()
+        }
+        Err(e) => {
+            eprintln!("Failed to load HTML: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+fn app_pages_only(script: &str, start_url: &str) -> String {
+    let dev_origin = Url::parse(start_url)
+        .ok()
+        .map(|url| url.origin())
+        .filter(|origin| origin.is_tuple())
+        .map(|origin| origin.ascii_serialization());
+    format!(
+        "if (location.protocol === 'application:' || location.origin === {}) {{{}}}",
+        serde_json::to_string(&dev_origin).unwrap_or_default(),
+        script
+    )
+}
+
+// Readable by the current user only, replacing whatever token an earlier run left
+fn write_token(path: &Path, token: &str) -> Result<(), io::Error> {
+    let _ = fs::remove_file(path);
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)?;
+    file.write_all(token.as_bytes())
+}
+
+// Rejects requests for any host but this server's loopback address, so a DNS-rebound
+// site can't read the token-bearing page
+fn local_only(router: Router, port: u16) -> Router {
+    router.layer(middleware::from_fn_with_state(port, check_host))
+}
+
+async fn check_host<B>(State(port): State<u16>, request: Request<B>, next: Next<B>) -> Response {
+    let host = request
+        .headers()
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok());
+    if !host.is_some_and(|host| is_local_host(host, port)) {
+        eprintln!("Rejected request for host {:?}", host.unwrap_or_default());
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    next.run(request).await
+}
+
+fn is_local_host(host: &str, port: u16) -> bool {
+    host == format!("127.0.0.1:{}", port) || host == format!("localhost:{}", port)
+}
+
+// Just past the opening <head> tag, whatever its case or attributes
+fn head_end(html: &str) -> Option<usize> {
+    let lower = html.to_ascii_lowercase();
+    let start = lower
+        .match_indices("<head")
+        .map(|(index, _)| index)
+        .find(|&index| {
+            lower[index + 5..].starts_with(|c: char| c == '>' || c.is_ascii_whitespace())
+        })?;
+    lower[start..].find('>').map(|end| start + end + 1)
+}
+
+// The build output has files that aren't part of the app, like .vite/manifest.json
+async fn hide_dotfiles<B>(request: Request<B>, next: Next<B>) -> Response {
+    let hidden =
+        request.uri().path().split('/').any(|segment| {
+            segment.starts_with('.') || segment.to_ascii_lowercase().starts_with("%2e")
+        });
+    if hidden {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    next.run(request).await
+}
+
+// Same rules as the application protocol: routes get the app, missing files a 404
+async fn spa_fallback(state: State<(ServerState, IpcState)>, uri: Uri) -> Response {
+    if Path::new(uri.path()).extension().is_some() {
+        eprintln!("Failed to load asset {}: not found", uri.path());
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    index(state).await
+}
+
+async fn ipc_http(State(ipc): State<IpcState>, headers: HeaderMap, body: String) -> Response {
+    if !ipc.is_authorized(
+        headers
+            .get(TOKEN_HEADER)
+            .and_then(|value| value.to_str().ok()),
+    ) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+
+    // Requiring JSON forces a CORS preflight, so other sites can't post here
+    let is_json = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if !is_json {
+        return StatusCode::UNSUPPORTED_MEDIA_TYPE.into_response();
+    }
+
+    let handler = ipc.protocol_handler.clone();
+    match tokio::task::spawn_blocking(move || ipc::dispatch(&body, handler.as_ref())).await {
+        Ok(response) => Json(response).into_response(),
+        Err(e) => {
+            eprintln!("IPC handler failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn ipc_ws(
+    State(ipc): State<IpcState>,
+    Query(query): Query<TokenQuery>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    // Browsers don't apply CORS to WebSockets, so the per-launch token is the gate
+    if !ipc.is_authorized(query.token.as_deref()) {
+        eprintln!("Rejected IPC WebSocket without a valid token");
+        return StatusCode::FORBIDDEN.into_response();
+    }
+
+    ws.on_upgrade(move |socket| ipc_socket(socket, ipc))
+}
+
+// Requests are answered in order by a worker task, so a slow handler never
+// holds up pushed events and a burst of requests never blocks reading.
+// At shutdown the socket stops reading, answers what it has already read and closes.
+async fn ipc_socket(mut socket: WebSocket, ipc: IpcState) {
+    let (request_tx, mut request_rx) = mpsc::unbounded_channel::<String>();
+    let (response_tx, mut response_rx) = mpsc::unbounded_channel::<String>();
+    let mut events = ipc.events.subscribe();
+    let mut closing = ipc.closing.subscribe();
+    let mut request_tx = Some(request_tx);
+
+    let handler = ipc.protocol_handler.clone();
+    let worker = tokio::spawn(async move {
+        while let Some(body) = request_rx.recv().await {
+            let handler = handler.clone();
+            let response =
+                match tokio::task::spawn_blocking(move || ipc::dispatch(&body, handler.as_ref()))
+                    .await
+                {
+                    Ok(response) => response,
+                    Err(e) => {
+                        eprintln!("IPC handler failed: {}", e);
+                        break;
+                    }
+                };
+            let json = serde_json::to_string(&response).unwrap_or_default();
+            if response_tx.send(json).is_err() {
+                break;
+            }
+        }
+    });
+
+    loop {
+        let outgoing = tokio::select! {
+            // Dropping the sender lets the worker finish the queue and then stop
+            _ = closing.wait_for(|closing| *closing), if request_tx.is_some() => {
+                request_tx = None;
+                continue;
+            }
+            message = socket.recv(), if request_tx.is_some() => match message {
+                Some(Ok(Message::Text(body))) => {
+                    if request_tx.as_ref().is_some_and(|tx| tx.send(body).is_err()) {
+                        break;
+                    }
+                    continue;
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => continue,
+            },
+            response = response_rx.recv() => match response {
+                Some(json) => json,
+                None => break,
+            },
+            event = events.recv() => match event {
+                Ok(json) => json,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    eprintln!("IPC WebSocket client fell behind, dropped {} events", skipped);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+        };
+
+        if socket.send(Message::Text(outgoing)).await.is_err() {
+            break;
+        }
+    }
+
+    worker.abort();
+    // Events emitted before the shutdown still reach the page
+    if request_tx.is_none() {
+        while let Ok(json) = events.try_recv() {
+            if socket.send(Message::Text(json)).await.is_err() {
+                break;
+            }
+        }
+        let _ = socket.close().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_end_of_the_head_tag() {
+        assert_eq!(head_end("<html><head><title>"), Some(12));
+        assert_eq!(head_end(r#"<HEAD lang="en">x"#), Some(16));
+        assert_eq!(head_end("<header></header><head>"), Some(23));
+        assert_eq!(head_end("<body></body>"), None);
+    }
+
+    #[test]
+    fn accepts_only_loopback_hosts_on_the_port() {
+        assert!(is_local_host("127.0.0.1:3030", 3030));
+        assert!(is_local_host("localhost:3030", 3030));
+        assert!(!is_local_host("localhost:3031", 3030));
+        assert!(!is_local_host("localhost", 3030));
+        assert!(!is_local_host("attacker.example:3030", 3030));
+    }
+
+    #[test]
+    fn client_script_is_limited_to_app_origins() {
+        assert_eq!(
+            app_pages_only("run();", "application://index.html"),
+            "if (location.protocol === 'application:' || location.origin === null) {run();}"
+        );
+        assert_eq!(
+            app_pages_only("run();", "http://localhost:5173/"),
+            "if (location.protocol === 'application:' || location.origin === \"http://localhost:5173\") {run();}"
+        );
+    }
+}
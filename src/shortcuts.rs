@@ -0,0 +1,192 @@
+use std::{error::Error, fmt};
+
+use muda::accelerator::Accelerator;
+use serde::Serialize;
+
+use crate::{events, menu::MenuEntry};
+
+// Run in Rust, or sent to the frontend as a `rust-event` with event 'shortcut'
+pub enum ShortcutAction {
+    Run(Box<dyn Fn()>),
+    Emit,
+}
+
+pub struct Shortcut {
+    pub id: String,
+    pub title: String,
+    pub accelerator: String,
+    parsed: Accelerator,
+    action: ShortcutAction,
+}
+
+#[derive(Debug)]
+pub enum ShortcutError {
+    DuplicateId(String),
+    Accelerator {
+        id: String,
+        message: String,
+    },
+    Conflict {
+        accelerator: String,
+        id: String,
+        existing: String,
+    },
+}
+
+impl fmt::Display for ShortcutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DuplicateId(id) => write!(f, "Shortcut id used twice: {}", id),
+            Self::Accelerator { id, message } => {
+                write!(f, "Invalid accelerator for shortcut {}: {}", id, message)
+            }
+            Self::Conflict {
+                accelerator,
+                id,
+                existing,
+            } => write!(
+                f,
+                "Shortcut {} uses {}, which is already taken by {}",
+                id, accelerator, existing
+            ),
+        }
+    }
+}
+
+impl Error for ShortcutError {}
+
+#[derive(Serialize)]
+struct ShortcutEvent<'a> {
+    id: &'a str,
+}
+
+// App-level keyboard shortcuts. They are installed as menu items, because the menu is what
+// sees key presses first on every platform, whether the webview, a menu or a native dialog
+// has focus. The menu decides where they show up with a `shortcuts` entry.
+#[derive(Default)]
+pub struct ShortcutRegistry {
+    shortcuts: Vec<Shortcut>,
+}
+
+impl ShortcutRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Accelerators use muda's syntax, e.g. "CmdOrCtrl+K"; the same key combination can only
+    // be registered once, however it is spelled
+    pub fn register(
+        &mut self,
+        id: &str,
+        title: &str,
+        accelerator: &str,
+        action: ShortcutAction,
+    ) -> Result<&mut Self, ShortcutError> {
+        if self.get(id).is_some() {
+            return Err(ShortcutError::DuplicateId(id.to_string()));
+        }
+        let parsed: Accelerator =
+            accelerator
+                .parse()
+                .map_err(|e: muda::accelerator::AcceleratorParseError| {
+                    ShortcutError::Accelerator {
+                        id: id.to_string(),
+                        message: e.to_string(),
+                    }
+                })?;
+        if let Some(existing) = self.shortcuts.iter().find(|s| s.parsed == parsed) {
+            return Err(ShortcutError::Conflict {
+                accelerator: accelerator.to_string(),
+                id: id.to_string(),
+                existing: existing.id.clone(),
+            });
+        }
+
+        self.shortcuts.push(Shortcut {
+            id: id.to_string(),
+            title: title.to_string(),
+            accelerator: accelerator.to_string(),
+            parsed,
+            action,
+        });
+        Ok(self)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Shortcut> {
+        self.shortcuts.iter().find(|shortcut| shortcut.id == id)
+    }
+
+    // The items the menu shows in place of its `shortcuts` entry. Conflicts with the menu's own
+    // accelerators are caught when the menu is built.
+    pub fn menu_items(&self) -> Vec<MenuEntry> {
+        self.shortcuts
+            .iter()
+            .map(|shortcut| {
+                MenuEntry::item(&shortcut.id, &shortcut.title).accelerator(&shortcut.accelerator)
+            })
+            .collect()
+    }
+}
+
+impl Shortcut {
+    // Runs the handler, or returns the script that forwards the shortcut to the frontend
+    pub fn trigger(&self) -> Option<String> {
+        match &self.action {
+            ShortcutAction::Run(handler) => {
+                handler();
+                None
+            }
+            ShortcutAction::Emit => Some(events::dispatch_script(
+                "shortcut",
+                &ShortcutEvent { id: &self.id },
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry(shortcuts: &[(&str, &str)]) -> Result<ShortcutRegistry, ShortcutError> {
+        let mut registry = ShortcutRegistry::new();
+        for (id, accelerator) in shortcuts {
+            registry.register(id, id, accelerator, ShortcutAction::Emit)?;
+        }
+        Ok(registry)
+    }
+
+    #[test]
+    fn rejects_duplicate_ids() {
+        let result = registry(&[("palette", "CmdOrCtrl+K"), ("palette", "CmdOrCtrl+P")]);
+        assert!(matches!(result, Err(ShortcutError::DuplicateId(id)) if id == "palette"));
+    }
+
+    #[test]
+    fn rejects_invalid_accelerators() {
+        let result = registry(&[("palette", "Ctrl+Nope")]);
+        assert!(matches!(result, Err(ShortcutError::Accelerator { id, .. }) if id == "palette"));
+    }
+
+    #[test]
+    fn rejects_the_same_keys_however_they_are_spelled() {
+        let result = registry(&[("palette", "Ctrl+Shift+K"), ("search", "shift+control+k")]);
+        assert!(matches!(
+            result,
+            Err(ShortcutError::Conflict { id, existing, .. }) if id == "search" && existing == "palette"
+        ));
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn cmd_or_ctrl_is_ctrl_off_macos() {
+        let result = registry(&[("palette", "CmdOrCtrl+K"), ("search", "ctrl+k")]);
+        assert!(matches!(result, Err(ShortcutError::Conflict { .. })));
+    }
+
+    #[test]
+    fn allows_different_modifiers() {
+        let registry = registry(&[("palette", "CmdOrCtrl+K"), ("search", "CmdOrCtrl+Shift+K")]);
+        assert_eq!(registry.unwrap().menu_items().len(), 2);
+    }
+}
@@ -0,0 +1,147 @@
+use std::{
+    io,
+    panic::{self, AssertUnwindSafe},
+    process, thread,
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use signal_hook::{
+    consts::{SIGINT, SIGTERM},
+    iterator::Signals,
+};
+
+// Everything the shutdown sequence gets before the app exits anyway. Stays below the
+// supervisor's timeout, so a supervised app is never killed halfway through.
+pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(3);
+
+type Hook = Box<dyn FnOnce() + Send>;
+
+// Rust code to run before the app exits, such as flushing a cache to disk
+#[derive(Default)]
+pub struct ShutdownHooks {
+    hooks: Vec<(String, Hook)>,
+}
+
+impl ShutdownHooks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register<F>(&mut self, name: &str, hook: F) -> &mut Self
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.hooks.push((name.to_string(), Box::new(hook)));
+        self
+    }
+
+    // Hooks run in registration order on their own thread, so one that hangs can't keep the
+    // process alive. It is abandoned at the deadline, together with the hooks after it.
+    pub fn run(self, deadline: Instant) {
+        if self.hooks.is_empty() {
+            return;
+        }
+
+        let (started_tx, started_rx) = crossbeam_channel::unbounded();
+        thread::spawn(move || {
+            for (name, hook) in self.hooks {
+                if started_tx.send(name.clone()).is_err() {
+                    return;
+                }
+                if panic::catch_unwind(AssertUnwindSafe(hook)).is_err() {
+                    eprintln!("Shutdown hook {} panicked", name);
+                }
+            }
+        });
+
+        let mut running = String::new();
+        loop {
+            match started_rx.recv_deadline(deadline) {
+                Ok(name) => running = name,
+                Err(RecvTimeoutError::Disconnected) => return,
+                Err(RecvTimeoutError::Timeout) => {
+                    eprintln!("Shutdown hook {} did not finish in time", running);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+// SIGTERM and SIGINT, e.g. from `kill` or Ctrl-C in the terminal. A second signal while the
+// app is still shutting down exits at once.
+pub fn signals() -> Result<Receiver<i32>, io::Error> {
+    let mut signals = Signals::new([SIGTERM, SIGINT])?;
+
+    let (tx, rx) = crossbeam_channel::unbounded();
+    thread::spawn(move || {
+        let mut signals = signals.forever();
+        if let Some(signal) = signals.next() {
+            println!("Received signal {}, shutting down", signal);
+            if tx.send(signal).is_err() {
+                return;
+            }
+        }
+        if signals.next().is_some() {
+            eprintln!("Received a second signal, exiting without finishing the shutdown");
+            process::exit(1);
+        }
+    });
+    Ok(rx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn runs_hooks_in_order_until_they_are_done() {
+        let ran = Arc::new(Mutex::new(Vec::new()));
+        let mut hooks = ShutdownHooks::new();
+        for name in ["first", "second", "third"] {
+            let ran = ran.clone();
+            hooks.register(name, move || ran.lock().unwrap().push(name));
+        }
+
+        let started = Instant::now();
+        hooks.run(started + SHUTDOWN_TIMEOUT);
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(*ran.lock().unwrap(), ["first", "second", "third"]);
+    }
+
+    #[test]
+    fn keeps_going_after_a_hook_panics() {
+        let (ran_tx, ran_rx) = crossbeam_channel::unbounded();
+        let mut hooks = ShutdownHooks::new();
+        hooks
+            .register("broken", || panic!("hook failed"))
+            .register("after", move || ran_tx.send(()).unwrap());
+
+        hooks.run(Instant::now() + SHUTDOWN_TIMEOUT);
+        assert!(ran_rx.try_recv().is_ok());
+    }
+
+    #[test]
+    fn abandons_a_stuck_hook_and_the_hooks_after_it_at_the_deadline() {
+        let (release_tx, release_rx) = crossbeam_channel::unbounded::<()>();
+        let (ran_tx, ran_rx) = crossbeam_channel::unbounded();
+        let mut hooks = ShutdownHooks::new();
+        hooks
+            .register("stuck", move || {
+                let _ = release_rx.recv();
+            })
+            .register("after", move || ran_tx.send(()).unwrap());
+
+        let started = Instant::now();
+        hooks.run(started + SHUTDOWN_TIMEOUT);
+        let elapsed = started.elapsed();
+        assert!(elapsed >= SHUTDOWN_TIMEOUT);
+        assert!(elapsed < SHUTDOWN_TIMEOUT + Duration::from_secs(1));
+
+        // Let the stuck hook finish; the one after it still never runs
+        drop(release_tx);
+        assert!(ran_rx.recv_timeout(Duration::from_millis(200)).is_err());
+    }
+}
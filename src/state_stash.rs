@@ -0,0 +1,175 @@
+use std::{
+    sync::{mpsc, Mutex},
+    time::Duration,
+};
+
+use crossbeam_channel::{Receiver, Sender};
+
+use crate::ipc::IpcRequest;
+
+// IPC functions used by the client script; they never reach the ProtocolHandler
+const STASH_FUNCTION: &str = "__rust_gui_stash_state";
+const RESTORE_FUNCTION: &str = "__rust_gui_restore_state";
+
+// How long a reload waits for the page to hand over its state
+pub const STASH_TIMEOUT: Duration = Duration::from_millis(500);
+
+// Asks the page to send its registered stores to Rust, see CLIENT_SCRIPT
+pub const STASH_SCRIPT: &str = "window.__rustGuiState?.stash();";
+
+// window.rustState.register(name, { save, restore }) opts a store into surviving reloads.
+// save() returns anything JSON-serializable; restore(value) gets it back after the reload.
+pub const CLIENT_SCRIPT: &str = r#"
+(() => {
+    const stores = new Map();
+    let snapshot = null;
+    const send = (fn, args) => window.ipc?.postMessage(JSON.stringify({ function: fn, args }));
+    const hydrate = (name, store) => {
+        if (!snapshot || !(name in snapshot)) return;
+        try {
+            store.restore(snapshot[name]);
+        } catch (e) {
+            console.error(`Failed to restore state for ${name}`, e);
+        }
+        delete snapshot[name];
+    };
+
+    window.__rustGuiState = {
+        stash() {
+            const state = {};
+            for (const [name, store] of stores) {
+                try {
+                    state[name] = store.save();
+                } catch (e) {
+                    console.error(`Failed to save state for ${name}`, e);
+                }
+            }
+            send('__rust_gui_stash_state', [JSON.stringify(state)]);
+        },
+        restore(state) {
+            snapshot = state;
+            stores.forEach((store, name) => hydrate(name, store));
+        },
+    };
+    window.rustState = {
+        register(name, store) {
+            stores.set(name, store);
+            hydrate(name, store);
+        },
+    };
+
+    send('__rust_gui_restore_state', []);
+})();
+"#;
+
+// Frontend state held in Rust while the page reloads
+pub struct StateStash {
+    snapshot: Mutex<Option<String>>,
+    stashed_tx: Sender<()>,
+}
+
+impl StateStash {
+    // The receiver fires once the page has stashed its state and can be reloaded
+    pub fn new() -> (Self, Receiver<()>) {
+        let (stashed_tx, stashed_rx) = crossbeam_channel::unbounded();
+        let stash = Self {
+            snapshot: Mutex::new(None),
+            stashed_tx,
+        };
+        (stash, stashed_rx)
+    }
+
+    // Handles the client script's own IPC messages, returning false for everything else
+    pub fn intercept(&self, body: &str, tx: &mpsc::Sender<String>) -> bool {
+        let Ok(request) = serde_json::from_str::<IpcRequest>(body) else {
+            return false;
+        };
+
+        match request.function.as_str() {
+            STASH_FUNCTION => {
+                // Re-serialized so only JSON ever gets evaluated back into the page
+                let snapshot = request
+                    .args
+                    .first()
+                    .and_then(|state| serde_json::from_str::<serde_json::Value>(state).ok())
+                    .map(|state| state.to_string());
+                *self.snapshot.lock().unwrap() = snapshot;
+                let _ = self.stashed_tx.send(());
+                true
+            }
+            // Each snapshot is restored once, so a manual reload later starts clean
+            RESTORE_FUNCTION => {
+                let snapshot = self.snapshot.lock().unwrap().take();
+                let js = format!(
+                    "window.__rustGuiState?.restore({});",
+                    snapshot.as_deref().unwrap_or("null")
+                );
+                if let Err(e) = tx.send(js) {
+                    eprintln!("Failed to send state snapshot: {}", e);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ipc(function: &str, args: &[&str]) -> String {
+        serde_json::json!({ "function": function, "args": args }).to_string()
+    }
+
+    #[test]
+    fn stashes_and_restores_a_snapshot_once() {
+        let (stash, stashed_rx) = StateStash::new();
+        let (tx, rx) = mpsc::channel();
+
+        let state = r#"{ "form": { "name": "Ada" } }"#;
+        assert!(stash.intercept(&ipc(STASH_FUNCTION, &[state]), &tx));
+        assert!(stashed_rx.try_recv().is_ok());
+        assert!(rx.try_recv().is_err());
+
+        assert!(stash.intercept(&ipc(RESTORE_FUNCTION, &[]), &tx));
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            r#"window.__rustGuiState?.restore({"form":{"name":"Ada"}});"#
+        );
+        assert!(stash.intercept(&ipc(RESTORE_FUNCTION, &[]), &tx));
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            "window.__rustGuiState?.restore(null);"
+        );
+    }
+
+    #[test]
+    fn passes_other_ipc_through() {
+        let (stash, stashed_rx) = StateStash::new();
+        let (tx, rx) = mpsc::channel();
+
+        assert!(!stash.intercept(&ipc("greet", &["Ada"]), &tx));
+        assert!(!stash.intercept("not json", &tx));
+        assert!(stashed_rx.try_recv().is_err());
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn malformed_state_is_dropped() {
+        let (stash, stashed_rx) = StateStash::new();
+        let (tx, rx) = mpsc::channel();
+
+        // The reload still goes ahead, without a snapshot
+        assert!(stash.intercept(&ipc(STASH_FUNCTION, &["{ not json"]), &tx));
+        assert!(stashed_rx.try_recv().is_ok());
+        assert!(stash.intercept(&ipc(STASH_FUNCTION, &[]), &tx));
+        assert!(stashed_rx.try_recv().is_ok());
+
+        assert!(stash.intercept(&ipc(RESTORE_FUNCTION, &[]), &tx));
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            "window.__rustGuiState?.restore(null);"
+        );
+    }
+}
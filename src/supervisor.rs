@@ -0,0 +1,317 @@
+use std::{
+    env,
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use serde::{Deserialize, Serialize};
+
+use crate::watcher::{AssetWatcher, ChangeSet, WatchConfig};
+
+// Set on the app process so it listens to the supervisor on stdin
+const SUPERVISED_ENV: &str = "RUST_GUI_SUPERVISED";
+// Saving a file in an editor touches it several times, and cargo is slow to start anyway
+const REBUILD_DEBOUNCE: Duration = Duration::from_millis(300);
+// How long the old app gets to stop Vite and exit before it is killed
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+// Cargo marks the root of every target directory with this file
+const CACHEDIR_TAG: &str = "CACHEDIR.TAG";
+
+// Line-delimited JSON the supervisor writes to the app's stdin
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum SupervisorMessage {
+    BuildStarted,
+    BuildFailed { output: String },
+    Shutdown,
+}
+
+struct App {
+    child: Child,
+    stdin: ChildStdin,
+}
+
+// `--supervise` runs the app as a child, rebuilding and restarting it when src/ changes
+pub fn is_requested() -> bool {
+    env::args().any(|arg| arg == "--supervise")
+}
+
+pub fn run() -> Result<(), io::Error> {
+    let exe = env::current_exe()?;
+    let args: Vec<String> = env::args().skip(1).filter(|a| a != "--supervise").collect();
+
+    let (changes_tx, changes_rx) = crossbeam_channel::unbounded();
+    let config = WatchConfig {
+        roots: vec![PathBuf::from("src")],
+        include: vec!["**/*.rs".to_string()],
+        debounce: REBUILD_DEBOUNCE,
+        ..WatchConfig::default()
+    };
+    let _watcher = AssetWatcher::spawn(&config, changes_tx).map_err(io::Error::other)?;
+
+    let mut app: Option<App> = None;
+    let result = loop {
+        if let Some(app) = &mut app {
+            app.send(&SupervisorMessage::BuildStarted);
+        }
+
+        match build(&exe) {
+            Ok(()) => {
+                if let Some(old) = app.take() {
+                    old.stop();
+                }
+                match App::spawn(&exe, &args) {
+                    Ok(started) => app = Some(started),
+                    Err(e) => eprintln!("Failed to start app: {}", e),
+                }
+            }
+            // The old app keeps running and shows the errors until the next build
+            Err(output) => match &mut app {
+                Some(app) => app.send(&SupervisorMessage::BuildFailed { output }),
+                None => eprintln!("Build failed, waiting for changes"),
+            },
+        }
+
+        match wait_for_changes(&mut app, &changes_rx) {
+            Ok(true) => continue,
+            Ok(false) => break Ok(()),
+            Err(e) => break Err(e),
+        }
+    };
+
+    if let Some(app) = app {
+        app.stop();
+    }
+    result
+}
+
+// Runs `cargo build` for the running executable, echoing its output and returning it on
+// failure
+fn build(exe: &Path) -> Result<(), String> {
+    println!("[supervisor] Building");
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let output = match Command::new(cargo)
+        .args(["build", "--color", "never"])
+        .args(build_args(exe))
+        .stdin(Stdio::null())
+        .output()
+    {
+        Ok(output) => output,
+        Err(e) => return Err(format!("Failed to run cargo: {}", e)),
+    };
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    eprint!("{}", stderr);
+    if output.status.success() {
+        return Ok(());
+    }
+
+    Err(compiler_errors(&stderr).to_string())
+}
+
+// Rebuilds the binary this process was started from rather than the default debug one:
+// the same target directory, profile and target, read back from where cargo put it.
+// Nothing is added for an executable outside a target directory.
+fn build_args(exe: &Path) -> Vec<String> {
+    let Some(profile_dir) = exe.parent() else {
+        return Vec::new();
+    };
+    let Some(above) = profile_dir.parent() else {
+        return Vec::new();
+    };
+    // target/<profile>/app, or target/<triple>/<profile>/app for cross builds
+    let (target_dir, triple) = if above.join(CACHEDIR_TAG).is_file() {
+        (above, None)
+    } else {
+        match above.parent() {
+            Some(target_dir) if target_dir.join(CACHEDIR_TAG).is_file() => {
+                (target_dir, above.file_name())
+            }
+            _ => return Vec::new(),
+        }
+    };
+
+    let mut args = Vec::new();
+    match profile_dir.file_name().and_then(|name| name.to_str()) {
+        Some("debug") | None => (),
+        Some("release") => args.push("--release".to_string()),
+        Some(profile) => args.extend(["--profile".to_string(), profile.to_string()]),
+    }
+    if let Some(triple) = triple {
+        args.extend([
+            "--target".to_string(),
+            triple.to_string_lossy().into_owned(),
+        ]);
+    }
+    args.extend([
+        "--target-dir".to_string(),
+        target_dir.to_string_lossy().into_owned(),
+    ]);
+    args
+}
+
+// Progress lines like "Compiling ..." only get in the way, so the output starts at the
+// first diagnostic line
+fn compiler_errors(stderr: &str) -> &str {
+    let mut offset = 0;
+    for line in stderr.split_inclusive('\n') {
+        if line.starts_with("error:") || line.starts_with("error[") {
+            return stderr[offset..].trim_end();
+        }
+        offset += line.len();
+    }
+    stderr.trim_end()
+}
+
+// Blocks until sources change (true) or the app was closed by the user (false)
+fn wait_for_changes(
+    app: &mut Option<App>,
+    changes_rx: &Receiver<ChangeSet>,
+) -> Result<bool, io::Error> {
+    loop {
+        if let Some(running) = app {
+            if let Some(status) = running.child.try_wait()? {
+                if status.success() {
+                    println!("[supervisor] App exited, stopping");
+                    return Ok(false);
+                }
+                // A crash doesn't end the session, the fix is probably on its way
+                eprintln!(
+                    "[supervisor] App exited with {}, waiting for changes",
+                    status
+                );
+                *app = None;
+            }
+        }
+
+        match changes_rx.recv_timeout(POLL_INTERVAL) {
+            Ok(changes) => {
+                println!("[supervisor] Rust sources changed: {}", changes.describe());
+                return Ok(true);
+            }
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => return Ok(false),
+        }
+    }
+}
+
+impl App {
+    fn spawn(exe: &Path, args: &[String]) -> Result<Self, io::Error> {
+        let mut child = Command::new(exe)
+            .args(args)
+            .env(SUPERVISED_ENV, "1")
+            .stdin(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        Ok(Self { child, stdin })
+    }
+
+    fn send(&mut self, message: &SupervisorMessage) {
+        let json = serde_json::to_string(message).unwrap_or_default();
+        if let Err(e) = writeln!(self.stdin, "{}", json) {
+            eprintln!("Failed to message app: {}", e);
+        }
+    }
+
+    // Asks the app to shut down cleanly so it stops its dev server, then kills it
+    fn stop(mut self) {
+        self.send(&SupervisorMessage::Shutdown);
+        let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+        while Instant::now() < deadline {
+            match self.child.try_wait() {
+                Ok(Some(_)) => return,
+                Ok(None) => thread::sleep(Duration::from_millis(50)),
+                Err(_) => break,
+            }
+        }
+        eprintln!("[supervisor] App did not exit in time, killing it");
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+// In the app: messages from the supervisor, if there is one. A closed stdin means the
+// supervisor went away, which shuts the app down too.
+pub fn messages() -> Option<Receiver<SupervisorMessage>> {
+    env::var_os(SUPERVISED_ENV)?;
+
+    let (tx, rx) = crossbeam_channel::unbounded();
+    thread::spawn(move || {
+        for line in BufReader::new(io::stdin()).lines() {
+            let Ok(line) = line else { break };
+            match serde_json::from_str(&line) {
+                Ok(message) => {
+                    if tx.send(message).is_err() {
+                        return;
+                    }
+                }
+                Err(e) => eprintln!("Invalid supervisor message: {}", e),
+            }
+        }
+        let _ = tx.send(SupervisorMessage::Shutdown);
+    });
+    Some(rx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    #[test]
+    fn cuts_output_at_the_first_error_line() {
+        let stderr = "   Compiling thiserror v1.0.69\n   Compiling rust-gui v0.1.0\nerror[E0425]: cannot find value `x`\n --> src/main.rs:1:1\n\nerror: aborting\n";
+        assert_eq!(
+            compiler_errors(stderr),
+            "error[E0425]: cannot find value `x`\n --> src/main.rs:1:1\n\nerror: aborting"
+        );
+        assert_eq!(
+            compiler_errors("warning: unused\nerror: could not compile\n"),
+            "error: could not compile"
+        );
+        assert_eq!(compiler_errors("killed by signal\n"), "killed by signal");
+    }
+
+    #[test]
+    fn rebuilds_with_the_running_profile_and_target() {
+        let target = env::temp_dir().join(format!("rust-gui-target-{}", std::process::id()));
+        fs::create_dir_all(&target).unwrap();
+        fs::write(target.join(CACHEDIR_TAG), "").unwrap();
+        let target_dir = target.to_string_lossy().into_owned();
+
+        let args = |exe: PathBuf| build_args(&exe);
+        assert_eq!(
+            args(target.join("debug").join("rust-gui")),
+            ["--target-dir", &target_dir]
+        );
+        assert_eq!(
+            args(target.join("release").join("rust-gui")),
+            ["--release", "--target-dir", &target_dir]
+        );
+        assert_eq!(
+            args(
+                target
+                    .join("aarch64-apple-darwin")
+                    .join("profiling")
+                    .join("rust-gui")
+            ),
+            [
+                "--profile",
+                "profiling",
+                "--target",
+                "aarch64-apple-darwin",
+                "--target-dir",
+                &target_dir
+            ]
+        );
+        assert!(args(PathBuf::from("/usr/local/bin/rust-gui")).is_empty());
+
+        fs::remove_dir_all(&target).unwrap();
+    }
+}
@@ -0,0 +1,689 @@
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap},
+    env,
+    error::Error,
+    fmt, fs,
+    hash::{Hash, Hasher},
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, Weak},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::{RecvTimeoutError, Sender};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use notify::{Event as NotifyEvent, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::{assets::ASSETS_URL, overlay::BUILD_ERROR_FILE};
+
+// Quiet period after the last change before a reload; Vite writes a build in well under this
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(100);
+// A steady stream of changes still reloads at least this often, in debounce windows
+const MAX_DEBOUNCE_WINDOWS: u32 = 20;
+// How often roots that don't exist yet are looked for
+const MISSING_ROOT_POLL: Duration = Duration::from_millis(500);
+
+const DEFAULT_ROOTS: [&str; 1] = ["frontend/dist"];
+const DEFAULT_INCLUDE: [&str; 2] = ["**/*.{js,mjs,css,html}", BUILD_ERROR_FILE];
+// Sourcemaps, dotfiles and editor/temp files never warrant a reload
+const DEFAULT_EXCLUDE: [&str; 6] = [
+    "**/*.map", "**/.*", "**/.*/**", "**/*~", "**/*.tmp", "**/*.swp",
+];
+
+// Which directories are watched, and which files in them trigger a reload.
+// Globs match paths relative to their root, e.g. "assets/index-4f3a.js".
+#[derive(Debug, Clone)]
+pub struct WatchConfig {
+    pub roots: Vec<PathBuf>,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub debounce: Duration,
+}
+
+#[derive(Debug)]
+pub enum WatchError {
+    Notify(notify::Error),
+    Pattern(globset::Error),
+}
+
+impl fmt::Display for WatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Notify(e) => write!(f, "File watcher error: {}", e),
+            Self::Pattern(e) => write!(f, "Invalid watch pattern: {}", e),
+        }
+    }
+}
+
+impl Error for WatchError {}
+
+// Every file that changed during one burst, sent to the event loop as a single reload.
+// Keyed by the path relative to its root, with / separators.
+#[derive(Debug, Default)]
+pub struct ChangeSet {
+    pub files: BTreeMap<String, PathBuf>,
+}
+
+// How much of the page a change invalidates, from least to most disruptive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChangeKind {
+    Style,
+    Script,
+    Document,
+}
+
+impl ChangeKind {
+    // Anything that isn't a stylesheet or a page, images included, reloads the page
+    fn of(file: &str) -> Self {
+        if file.ends_with(".css") {
+            Self::Style
+        } else if file.ends_with(".html") {
+            Self::Document
+        } else {
+            Self::Script
+        }
+    }
+}
+
+impl ChangeSet {
+    // The most disruptive kind of change in the set, which decides how to update the page
+    pub fn kind(&self) -> Option<ChangeKind> {
+        self.files.keys().map(|file| ChangeKind::of(file)).max()
+    }
+
+    // Stylesheets that were written, as URL paths under the directory assets:// serves.
+    // Watch roots can be anywhere, so the keys alone don't say where a file is served.
+    pub fn stylesheets(&self, asset_root: &Path) -> Vec<String> {
+        let asset_root = asset_root
+            .canonicalize()
+            .unwrap_or_else(|_| asset_root.to_path_buf());
+        self.files
+            .iter()
+            .filter(|(file, path)| ChangeKind::of(file) == ChangeKind::Style && path.is_file())
+            .filter_map(|(_, path)| path.strip_prefix(&asset_root).ok())
+            .map(url_path)
+            .collect()
+    }
+
+    pub fn describe(&self) -> String {
+        self.files.keys().cloned().collect::<Vec<_>>().join(", ")
+    }
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        Self {
+            roots: DEFAULT_ROOTS.iter().map(PathBuf::from).collect(),
+            include: DEFAULT_INCLUDE.iter().map(|s| s.to_string()).collect(),
+            exclude: DEFAULT_EXCLUDE.iter().map(|s| s.to_string()).collect(),
+            debounce: DEFAULT_DEBOUNCE,
+        }
+    }
+}
+
+impl WatchConfig {
+    // RUST_GUI_WATCH_ROOTS (a path list), RUST_GUI_WATCH_INCLUDE and
+    // RUST_GUI_WATCH_EXCLUDE (comma-separated globs) and RUST_GUI_WATCH_DEBOUNCE_MS
+    // override the defaults
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Some(roots) = env::var_os("RUST_GUI_WATCH_ROOTS") {
+            config.roots = env::split_paths(&roots).collect();
+        }
+        if let Ok(include) = env::var("RUST_GUI_WATCH_INCLUDE") {
+            config.include = split_patterns(&include);
+        }
+        if let Ok(exclude) = env::var("RUST_GUI_WATCH_EXCLUDE") {
+            config.exclude = split_patterns(&exclude);
+        }
+        if let Ok(value) = env::var("RUST_GUI_WATCH_DEBOUNCE_MS") {
+            match value.parse() {
+                Ok(millis) => config.debounce = Duration::from_millis(millis),
+                Err(_) => eprintln!("Ignoring invalid RUST_GUI_WATCH_DEBOUNCE_MS: {}", value),
+            }
+        }
+        config
+    }
+}
+
+fn split_patterns(patterns: &str) -> Vec<String> {
+    patterns
+        .split(',')
+        .map(str::trim)
+        .filter(|pattern| !pattern.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// The include and exclude globs of a WatchConfig, resolved against the watched roots
+struct WatchFilter {
+    roots: Vec<PathBuf>,
+    include: GlobSet,
+    exclude: GlobSet,
+}
+
+impl WatchFilter {
+    fn new(roots: Vec<PathBuf>, config: &WatchConfig) -> Result<Self, globset::Error> {
+        let build = |patterns: &[String]| {
+            let mut set = GlobSetBuilder::new();
+            for pattern in patterns {
+                set.add(Glob::new(pattern)?);
+            }
+            set.build()
+        };
+
+        Ok(Self {
+            roots,
+            include: build(&config.include)?,
+            exclude: build(&config.exclude)?,
+        })
+    }
+
+    // The path's key in a ChangeSet, if it is in a root and passes the globs
+    fn file(&self, path: &Path) -> Option<String> {
+        let relative = self
+            .roots
+            .iter()
+            .find_map(|root| path.strip_prefix(root).ok())?;
+        if !self.include.is_match(relative) || self.exclude.is_match(relative) {
+            return None;
+        }
+        Some(url_path(relative))
+    }
+}
+
+// A relative path with / separators on every platform
+fn url_path(relative: &Path) -> String {
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// Replaces each matching <link> with one for the new file, then drops the old one once
+// the new one has loaded, so there is no flash of unstyled content. Vite's hashed names
+// ([name]-[hash].css) are matched by their name.
+pub fn stylesheet_swap_script(stylesheets: &[String]) -> String {
+    format!(
+        r#"
+        (() => {{
+            const base = (href) => href.split('?')[0].split('/').pop()
+                .replace(/\.css$/, '').replace(/-[\w-]{{8}}$/, '');
+            for (const file of {files}) {{
+                const link = [...document.querySelectorAll('link[rel="stylesheet"]')]
+                    .find((link) => base(link.href) === base(file));
+                if (!link) continue;
+                const next = link.cloneNode();
+                next.href = '{base_url}' + file + '?t=' + Date.now();
+                next.addEventListener('load', () => link.remove());
+                link.after(next);
+            }}
+        }})();
+        "#,
+        files = serde_json::to_string(stylesheets).unwrap_or_default(),
+        base_url = ASSETS_URL,
+    )
+}
+
+pub struct AssetWatcher {
+    // Dropping the notify watcher disconnects the debounce thread, which then exits
+    watcher: Arc<Mutex<RecommendedWatcher>>,
+}
+
+impl AssetWatcher {
+    pub fn spawn(config: &WatchConfig, changes_tx: Sender<ChangeSet>) -> Result<Self, WatchError> {
+        // Events report canonical paths on some platforms, so everything is keyed by those
+        let mut roots = Vec::new();
+        let mut missing = Vec::new();
+        for root in &config.roots {
+            match root.canonicalize() {
+                Ok(root) if root.is_dir() => roots.push(root),
+                Ok(_) => eprintln!("Not watching {}: not a directory", root.display()),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => match expected_path(root) {
+                    Some(expected) => {
+                        println!("{} doesn't exist yet, waiting for it", root.display());
+                        roots.push(expected.clone());
+                        missing.push(expected);
+                    }
+                    None => eprintln!("Not watching {}: {}", root.display(), e),
+                },
+                Err(e) => eprintln!("Not watching {}: {}", root.display(), e),
+            }
+        }
+        let filter = Arc::new(WatchFilter::new(roots, config).map_err(WatchError::Pattern)?);
+
+        let (path_tx, path_rx) = crossbeam_channel::unbounded::<(String, PathBuf)>();
+        let created_tx = path_tx.clone();
+        let mut watcher = notify::recommended_watcher({
+            let filter = filter.clone();
+            move |res: Result<NotifyEvent, notify::Error>| {
+                let event = match res {
+                    Ok(event) => event,
+                    Err(e) => {
+                        eprintln!("File watcher error: {}", e);
+                        return;
+                    }
+                };
+                if !(event.kind.is_modify() || event.kind.is_create() || event.kind.is_remove()) {
+                    return;
+                }
+
+                for path in event.paths {
+                    if let Some(file) = filter.file(&path) {
+                        let _ = path_tx.send((file, path));
+                    }
+                }
+            }
+        })
+        .map_err(WatchError::Notify)?;
+
+        let existing = filter.roots.iter().filter(|root| !missing.contains(root));
+        for root in existing.clone() {
+            match watcher.watch(root, RecursiveMode::Recursive) {
+                Ok(()) => println!("Watching {} for changes", root.display()),
+                Err(e) => eprintln!("Not watching {}: {}", root.display(), e),
+            }
+        }
+
+        // Vite rewrites files whose content didn't change, e.g. index.html on every build
+        let mut fingerprints = HashMap::new();
+        for root in existing {
+            seed_fingerprints(root, &filter, &mut fingerprints);
+        }
+
+        let mut debouncer = Debouncer::new(config.debounce);
+        thread::spawn(move || loop {
+            // Block for the first change of a burst, then keep collecting until it is due
+            let received = match debouncer.timeout(Instant::now()) {
+                Some(timeout) => path_rx.recv_timeout(timeout),
+                None => path_rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match received {
+                Ok((file, path)) => debouncer.add(file, path, Instant::now()),
+                Err(RecvTimeoutError::Timeout) => (),
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+            let Some(mut changes) = debouncer.take_due(Instant::now()) else {
+                continue;
+            };
+
+            changes.files.retain(|_, path| {
+                let fingerprint = fingerprint(path);
+                fingerprints.insert(path.clone(), fingerprint) != Some(fingerprint)
+            });
+            if changes.files.is_empty() {
+                continue;
+            }
+
+            if changes_tx.send(changes).is_err() {
+                return;
+            }
+        });
+
+        let watcher = Arc::new(Mutex::new(watcher));
+        if !missing.is_empty() {
+            watch_when_created(missing, Arc::downgrade(&watcher), filter, created_tx);
+        }
+
+        Ok(Self { watcher })
+    }
+
+    // Stops watching. The debounce thread isn't waited for: its last burst would only reach an
+    // event loop that is exiting.
+    pub fn stop(self) {
+        drop(self.watcher);
+    }
+}
+
+// Collects changes into bursts. A burst is due once nothing has changed for a debounce
+// interval, or after MAX_DEBOUNCE_WINDOWS intervals however busy it stays. The caller passes
+// the time in.
+struct Debouncer {
+    debounce: Duration,
+    burst: Option<Burst>,
+}
+
+struct Burst {
+    changes: ChangeSet,
+    started: Instant,
+    last_change: Instant,
+}
+
+impl Debouncer {
+    fn new(debounce: Duration) -> Self {
+        Self {
+            debounce,
+            burst: None,
+        }
+    }
+
+    fn add(&mut self, file: String, path: PathBuf, now: Instant) {
+        let burst = self.burst.get_or_insert_with(|| Burst {
+            changes: ChangeSet::default(),
+            started: now,
+            last_change: now,
+        });
+        burst.changes.files.insert(file, path);
+        burst.last_change = now;
+    }
+
+    // How long to wait for another change before the burst is due; None while idle
+    fn timeout(&self, now: Instant) -> Option<Duration> {
+        let burst = self.burst.as_ref()?;
+        Some(self.due(burst).saturating_duration_since(now))
+    }
+
+    fn take_due(&mut self, now: Instant) -> Option<ChangeSet> {
+        let burst = self.burst.as_ref()?;
+        if now < self.due(burst) {
+            return None;
+        }
+        self.burst.take().map(|burst| burst.changes)
+    }
+
+    fn due(&self, burst: &Burst) -> Instant {
+        (burst.last_change + self.debounce)
+            .min(burst.started + self.debounce * MAX_DEBOUNCE_WINDOWS)
+    }
+}
+
+// Roots that didn't exist at startup, like dist/ before the first build, are watched once
+// they appear. Their files count as changed, since nothing saw them being written.
+fn watch_when_created(
+    mut missing: Vec<PathBuf>,
+    watcher: Weak<Mutex<RecommendedWatcher>>,
+    filter: Arc<WatchFilter>,
+    path_tx: Sender<(String, PathBuf)>,
+) {
+    thread::spawn(move || {
+        while !missing.is_empty() {
+            thread::sleep(MISSING_ROOT_POLL);
+            // Gone once the AssetWatcher is dropped
+            let Some(watcher) = watcher.upgrade() else {
+                return;
+            };
+
+            missing.retain(|root| {
+                if !root.is_dir() {
+                    return true;
+                }
+                match watcher
+                    .lock()
+                    .unwrap()
+                    .watch(root, RecursiveMode::Recursive)
+                {
+                    Ok(()) => println!("Watching {} for changes", root.display()),
+                    Err(e) => {
+                        eprintln!("Not watching {}: {}", root.display(), e);
+                        return false;
+                    }
+                }
+
+                let mut files = HashMap::new();
+                seed_fingerprints(root, &filter, &mut files);
+                for path in files.into_keys() {
+                    if let Some(file) = filter.file(&path) {
+                        let _ = path_tx.send((file, path));
+                    }
+                }
+                false
+            });
+        }
+    });
+}
+
+// Where a directory that doesn't exist yet will be, by the canonical path of the nearest
+// ancestor that does
+fn expected_path(path: &Path) -> Option<PathBuf> {
+    let mut missing = Vec::new();
+    let mut ancestor = path;
+    loop {
+        let dir = if ancestor.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            ancestor
+        };
+        if let Ok(base) = dir.canonicalize() {
+            return Some(
+                missing
+                    .into_iter()
+                    .rev()
+                    .fold(base, |path, name| path.join(name)),
+            );
+        }
+        missing.push(ancestor.file_name()?);
+        ancestor = ancestor.parent()?;
+    }
+}
+
+// Content hash of a file, or None once it is gone
+fn fingerprint(path: &Path) -> Option<u64> {
+    let content = fs::read(path).ok()?;
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+fn seed_fingerprints(
+    dir: &Path,
+    filter: &WatchFilter,
+    fingerprints: &mut HashMap<PathBuf, Option<u64>>,
+) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("Failed to scan {}: {}", dir.display(), e);
+            return;
+        }
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            seed_fingerprints(&path, filter, fingerprints);
+        } else if filter.file(&path).is_some() {
+            fingerprints.insert(path.clone(), fingerprint(&path));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A fresh directory under the system temp dir, removed when the test ends
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = env::temp_dir().join(format!("rust-gui-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            Self(path.canonicalize().unwrap())
+        }
+
+        fn write(&self, relative: &str) -> PathBuf {
+            self.write_content(relative, "body {}")
+        }
+
+        fn write_content(&self, relative: &str, content: &str) -> PathBuf {
+            let path = self.0.join(relative);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, content).unwrap();
+            path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    const DEBOUNCE: Duration = Duration::from_millis(100);
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    fn change(debouncer: &mut Debouncer, file: &str, now: Instant) {
+        debouncer.add(file.to_string(), PathBuf::from(file), now);
+    }
+
+    #[test]
+    fn idle_debouncer_waits_for_the_first_change() {
+        let mut debouncer = Debouncer::new(DEBOUNCE);
+        let start = Instant::now();
+        assert_eq!(debouncer.timeout(start), None);
+        assert!(debouncer.take_due(start + ms(10_000)).is_none());
+    }
+
+    #[test]
+    fn burst_is_due_after_a_quiet_period() {
+        let mut debouncer = Debouncer::new(DEBOUNCE);
+        let start = Instant::now();
+        change(&mut debouncer, "index.js", start);
+
+        assert_eq!(debouncer.timeout(start), Some(DEBOUNCE));
+        assert_eq!(debouncer.timeout(start + ms(60)), Some(ms(40)));
+        assert!(debouncer.take_due(start + ms(99)).is_none());
+        let changes = debouncer.take_due(start + DEBOUNCE).unwrap();
+        assert_eq!(changes.describe(), "index.js");
+        assert_eq!(debouncer.timeout(start + DEBOUNCE), None);
+    }
+
+    #[test]
+    fn burst_collapses_into_one_change_set() {
+        let mut debouncer = Debouncer::new(DEBOUNCE);
+        let start = Instant::now();
+        change(&mut debouncer, "index.js", start);
+        change(&mut debouncer, "style.css", start + ms(50));
+        change(&mut debouncer, "index.js", start + ms(90));
+
+        // Every change restarts the quiet period
+        assert!(debouncer.take_due(start + ms(150)).is_none());
+        assert_eq!(debouncer.timeout(start + ms(150)), Some(ms(40)));
+        let changes = debouncer.take_due(start + ms(190)).unwrap();
+        assert_eq!(changes.describe(), "index.js, style.css");
+    }
+
+    #[test]
+    fn busy_burst_is_cut_after_the_window_cap() {
+        let mut debouncer = Debouncer::new(DEBOUNCE);
+        let start = Instant::now();
+        let cap = DEBOUNCE * MAX_DEBOUNCE_WINDOWS;
+        let mut elapsed = Duration::ZERO;
+        while elapsed < cap {
+            assert!(debouncer.take_due(start + elapsed).is_none());
+            change(&mut debouncer, "index.js", start + elapsed);
+            elapsed += ms(50);
+        }
+
+        assert_eq!(debouncer.timeout(start + cap - ms(10)), Some(ms(10)));
+        assert!(debouncer.take_due(start + cap).is_some());
+
+        // The next change starts a new burst
+        change(&mut debouncer, "style.css", start + cap + ms(10));
+        assert_eq!(debouncer.timeout(start + cap + ms(10)), Some(DEBOUNCE));
+    }
+
+    #[test]
+    fn stylesheets_are_resolved_against_the_asset_root() {
+        let dir = TempDir::new("stylesheets");
+        let mut changes = ChangeSet::default();
+        // Watching frontend/ while assets:// serves frontend/dist
+        for file in [
+            "dist/assets/index-a1b2c3d4.css",
+            "src/app.css",
+            "dist/index.html",
+        ] {
+            changes.files.insert(file.to_string(), dir.write(file));
+        }
+        changes
+            .files
+            .insert("dist/gone.css".to_string(), dir.0.join("dist/gone.css"));
+
+        assert_eq!(
+            changes.stylesheets(&dir.0.join("dist")),
+            ["assets/index-a1b2c3d4.css"]
+        );
+    }
+
+    #[test]
+    fn keys_changes_by_root_relative_path() {
+        let dir = TempDir::new("filter");
+        let filter = WatchFilter::new(vec![dir.0.clone()], &WatchConfig::default()).unwrap();
+
+        assert_eq!(
+            filter.file(&dir.0.join("assets").join("index.js")),
+            Some("assets/index.js".to_string())
+        );
+        assert_eq!(
+            filter.file(&dir.0.join("assets").join("index.js.map")),
+            None
+        );
+        assert_eq!(
+            filter.file(&dir.0.join(".vite").join("manifest.json")),
+            None
+        );
+        assert_eq!(filter.file(Path::new("/elsewhere/index.js")), None);
+    }
+
+    #[test]
+    fn expects_missing_roots_under_their_existing_ancestor() {
+        let dir = TempDir::new("expected");
+        assert_eq!(
+            expected_path(&dir.0.join("frontend").join("dist")),
+            Some(dir.0.join("frontend").join("dist"))
+        );
+        assert_eq!(expected_path(&dir.0), Some(dir.0.clone()));
+    }
+
+    #[test]
+    fn css_only_rebuild_swaps_styles_even_though_index_html_is_rewritten() {
+        let dir = TempDir::new("css-only");
+        let html = "<html><head></head><body></body></html>";
+        dir.write_content("index.html", html);
+        dir.write_content("assets/style.css", "body {}");
+        let config = WatchConfig {
+            roots: vec![dir.0.clone()],
+            debounce: Duration::from_millis(20),
+            ..WatchConfig::default()
+        };
+        let (changes_tx, changes_rx) = crossbeam_channel::unbounded();
+        let watcher = AssetWatcher::spawn(&config, changes_tx).unwrap();
+
+        // Vite writes every file of the build, index.html included, with the same content
+        dir.write_content("index.html", html);
+        dir.write_content("assets/style.css", "body { color: red }");
+        let changes = changes_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(changes.describe(), "assets/style.css");
+        assert_eq!(changes.kind(), Some(ChangeKind::Style));
+        watcher.stop();
+    }
+
+    #[test]
+    fn watches_a_root_once_it_is_created() {
+        let dir = TempDir::new("created");
+        let config = WatchConfig {
+            roots: vec![dir.0.join("dist")],
+            debounce: Duration::from_millis(20),
+            ..WatchConfig::default()
+        };
+        let (changes_tx, changes_rx) = crossbeam_channel::unbounded();
+        let watcher = AssetWatcher::spawn(&config, changes_tx).unwrap();
+
+        // Written before the root is watched, so it is picked up when the root appears
+        dir.write("dist/index.html");
+        let changes = changes_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(changes.describe(), "index.html");
+
+        // And from then on by the watch itself. A new subdirectory would race the watch on it.
+        dir.write("dist/index.js");
+        let changes = changes_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(changes.describe(), "index.js");
+        watcher.stop();
+    }
+}
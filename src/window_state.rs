@@ -0,0 +1,323 @@
+use std::{
+    collections::BTreeMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use tao::{
+    dpi::{LogicalSize, PhysicalPosition, PhysicalSize},
+    event_loop::EventLoopWindowTarget,
+    monitor::MonitorHandle,
+    window::{Window, WindowBuilder},
+};
+
+use crate::config::WindowConfig;
+
+// Overrides the per-app state file, e.g. to keep test runs apart
+const STATE_FILE_ENV: &str = "RUST_GUI_STATE_FILE";
+const STATE_FILE: &str = "window-state.json";
+
+// Where a window was last seen. Coordinates are physical pixels; the position is missing
+// where the platform doesn't report one, such as on Wayland.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowState {
+    pub position: Option<(i32, i32)>,
+    // The inner size; the outer one includes the title bar and borders
+    pub size: (u32, u32),
+    #[serde(default)]
+    pub outer_size: Option<(u32, u32)>,
+    pub maximized: bool,
+    pub monitor: Option<String>,
+}
+
+// Window states by label, kept in memory while the app runs and written on close
+pub struct WindowStateStore {
+    // None when there is nowhere to keep it
+    path: Option<PathBuf>,
+    states: BTreeMap<String, WindowState>,
+}
+
+impl WindowStateStore {
+    // A missing file means nothing to restore; a broken one is reported and ignored
+    pub fn load() -> Self {
+        let path = match state_file() {
+            Ok(path) => path,
+            Err(e) => {
+                eprintln!("Window state won't be saved: {}", e);
+                return Self {
+                    path: None,
+                    states: BTreeMap::new(),
+                };
+            }
+        };
+        let states = match fs::read(&path) {
+            Ok(content) => serde_json::from_slice(&content).unwrap_or_else(|e| {
+                eprintln!("Ignoring invalid window state {}: {}", path.display(), e);
+                BTreeMap::new()
+            }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => {
+                eprintln!("Failed to read window state {}: {}", path.display(), e);
+                BTreeMap::new()
+            }
+        };
+        Self {
+            path: Some(path),
+            states,
+        }
+    }
+
+    // Puts the window back where it was, pulled onto a monitor that still exists
+    pub fn restore<T: 'static>(
+        &self,
+        label: &str,
+        config: &WindowConfig,
+        mut builder: WindowBuilder,
+        target: &EventLoopWindowTarget<T>,
+    ) -> WindowBuilder {
+        let Some(state) = self.states.get(label) else {
+            return builder;
+        };
+        let monitors: Vec<MonitorHandle> = target.available_monitors().collect();
+        let monitor = monitors
+            .iter()
+            .find(|monitor| state.monitor.is_some() && monitor.name() == state.monitor)
+            .or_else(|| {
+                let position = state.position?;
+                monitors
+                    .iter()
+                    .find(|monitor| Rect::of(monitor).contains(position))
+            })
+            .cloned()
+            .or_else(|| target.primary_monitor())
+            .or_else(|| monitors.first().cloned());
+
+        // Fixed-size windows keep the size from their config
+        let (width, height) = match (&monitor, config.resizable) {
+            (_, true) => state.size,
+            (Some(monitor), false) => {
+                let size: PhysicalSize<u32> = LogicalSize::new(config.width, config.height)
+                    .to_physical(monitor.scale_factor());
+                (size.width, size.height)
+            }
+            (None, false) => state.size,
+        };
+        // The position is the outer one, so the title bar and borders count when fitting
+        let (frame_width, frame_height) = state.frame();
+        let (width, height) = match &monitor {
+            Some(monitor) => (
+                width.min(monitor.size().width.saturating_sub(frame_width)),
+                height.min(monitor.size().height.saturating_sub(frame_height)),
+            ),
+            None => (width, height),
+        };
+
+        if config.resizable {
+            builder = builder.with_inner_size(PhysicalSize::new(width.max(1), height.max(1)));
+        }
+        if let (Some(position), Some(monitor)) = (state.position, &monitor) {
+            let outer_size = (width + frame_width, height + frame_height);
+            let (x, y) = Rect::of(monitor).clamp(position, outer_size);
+            builder = builder.with_position(PhysicalPosition::new(x, y));
+        }
+        builder.with_maximized(state.maximized && config.resizable)
+    }
+
+    // Maximized and minimized windows keep the geometry they had before, so restoring a
+    // maximized window and then unmaximizing it lands somewhere sensible
+    pub fn capture(&mut self, label: &str, window: &Window) {
+        if window.is_minimized() {
+            return;
+        }
+        let maximized = window.is_maximized();
+        let monitor = window.current_monitor().and_then(|monitor| monitor.name());
+
+        if let (true, Some(state)) = (maximized, self.states.get_mut(label)) {
+            state.maximized = true;
+            state.monitor = monitor;
+            return;
+        }
+
+        let size = window.inner_size();
+        let outer_size = window.outer_size();
+        self.states.insert(
+            label.to_string(),
+            WindowState {
+                position: window
+                    .outer_position()
+                    .ok()
+                    .map(|position| (position.x, position.y)),
+                size: (size.width, size.height),
+                outer_size: Some((outer_size.width, outer_size.height)),
+                maximized,
+                monitor,
+            },
+        );
+    }
+
+    pub fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        if let Err(e) = write_json(path, &self.states) {
+            eprintln!("Failed to save window state {}: {}", path.display(), e);
+        }
+    }
+}
+
+impl WindowState {
+    // How much the title bar and borders add to the inner size; nothing for older state files
+    fn frame(&self) -> (u32, u32) {
+        let (width, height) = self.size;
+        let (outer_width, outer_height) = self.outer_size.unwrap_or(self.size);
+        (
+            outer_width.saturating_sub(width),
+            outer_height.saturating_sub(height),
+        )
+    }
+}
+
+// A monitor's area, or a window's, in physical pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Rect {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+}
+
+impl Rect {
+    fn of(monitor: &MonitorHandle) -> Self {
+        let position = monitor.position();
+        let size = monitor.size();
+        Self {
+            x: position.x,
+            y: position.y,
+            width: size.width,
+            height: size.height,
+        }
+    }
+
+    fn contains(&self, (x, y): (i32, i32)) -> bool {
+        (self.x..self.x + self.width as i32).contains(&x)
+            && (self.y..self.y + self.height as i32).contains(&y)
+    }
+
+    // Where a window of the given outer size goes so its top-left corner is on this rect and
+    // as much of it is visible as fits
+    fn clamp(&self, (x, y): (i32, i32), (width, height): (u32, u32)) -> (i32, i32) {
+        let max_x = self.x + self.width.saturating_sub(width) as i32;
+        let max_y = self.y + self.height.saturating_sub(height) as i32;
+        (x.clamp(self.x, max_x), y.clamp(self.y, max_y))
+    }
+}
+
+fn write_json(path: &Path, states: &BTreeMap<String, WindowState>) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_vec_pretty(states).map_err(io::Error::other)?;
+    fs::write(path, json)
+}
+
+// ~/Library/Application Support/<app> on macOS, the XDG state directory elsewhere
+fn state_file() -> io::Result<PathBuf> {
+    if let Some(path) = env::var_os(STATE_FILE_ENV) {
+        return Ok(PathBuf::from(path));
+    }
+
+    // XDG base directories must be absolute; a relative one is ignored
+    let xdg_state = env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute() && !cfg!(target_os = "macos"));
+    let base = match (xdg_state, env::var_os("HOME")) {
+        (Some(xdg_state), _) => xdg_state,
+        (None, Some(home)) if cfg!(target_os = "macos") => PathBuf::from(home)
+            .join("Library")
+            .join("Application Support"),
+        (None, Some(home)) => PathBuf::from(home).join(".local").join("state"),
+        (None, None) => {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("HOME is not set; set {} instead", STATE_FILE_ENV),
+            ))
+        }
+    };
+    Ok(base.join(env!("CARGO_PKG_NAME")).join(STATE_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRIMARY: Rect = Rect {
+        x: 0,
+        y: 0,
+        width: 1920,
+        height: 1080,
+    };
+    // A second monitor to the left of the primary one
+    const LEFT: Rect = Rect {
+        x: -1280,
+        y: 0,
+        width: 1280,
+        height: 1024,
+    };
+
+    #[test]
+    fn finds_the_monitor_a_position_is_on() {
+        assert!(PRIMARY.contains((0, 0)));
+        assert!(PRIMARY.contains((1919, 1079)));
+        assert!(!PRIMARY.contains((1920, 500)));
+        assert!(LEFT.contains((-1280, 0)));
+        assert!(!LEFT.contains((0, 0)));
+    }
+
+    #[test]
+    fn window_left_on_a_disconnected_monitor_moves_to_the_primary_one() {
+        // Last seen on an external monitor to the right, which is gone now
+        let position = (2400, 300);
+        assert!(![PRIMARY, LEFT].iter().any(|rect| rect.contains(position)));
+        assert_eq!(PRIMARY.clamp(position, (800, 600)), (1120, 300));
+    }
+
+    #[test]
+    fn partly_off_screen_window_is_pulled_back() {
+        assert_eq!(PRIMARY.clamp((1500, 900), (800, 600)), (1120, 480));
+        assert_eq!(PRIMARY.clamp((-200, -50), (800, 600)), (0, 0));
+        assert_eq!(LEFT.clamp((-1400, 800), (800, 600)), (-1280, 424));
+        // A window that is already visible stays put
+        assert_eq!(PRIMARY.clamp((100, 100), (800, 600)), (100, 100));
+    }
+
+    #[test]
+    fn window_larger_than_the_monitor_goes_to_its_corner() {
+        assert_eq!(PRIMARY.clamp((300, 300), (2560, 1440)), (0, 0));
+    }
+
+    #[test]
+    fn fits_the_outer_size_including_the_title_bar() {
+        let state = WindowState {
+            position: Some((1500, 900)),
+            size: (800, 600),
+            outer_size: Some((802, 630)),
+            maximized: false,
+            monitor: None,
+        };
+        let (frame_width, frame_height) = state.frame();
+        assert_eq!((frame_width, frame_height), (2, 30));
+        assert_eq!(
+            PRIMARY.clamp((1500, 900), (800 + frame_width, 600 + frame_height)),
+            (1118, 450)
+        );
+
+        // State files from before the outer size was kept have no frame
+        let old: WindowState = serde_json::from_str(
+            r#"{ "position": [0, 0], "size": [800, 600], "maximized": false, "monitor": null }"#,
+        )
+        .unwrap();
+        assert_eq!(old.frame(), (0, 0));
+    }
+}
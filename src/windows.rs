@@ -0,0 +1,696 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+};
+
+use muda::Menu;
+use serde::{Deserialize, Serialize};
+use tao::{
+    error::OsError,
+    event::WindowEvent,
+    event_loop::{EventLoopProxy, EventLoopWindowTarget},
+    window::{Theme, Window, WindowId},
+};
+use url::Url;
+use wry::{PageLoadEvent, Result as WryResult, WebView, WebViewBuilder};
+
+use crate::{
+    config::WindowConfig,
+    events::{self, UserEvent},
+    ipc::{self, ProtocolHandler},
+    menu,
+    platform::{Native, Platform},
+    schemes::SchemeRegistry,
+    security::SecurityPolicy,
+    state_stash::{self, StateStash},
+    window_state::WindowStateStore,
+};
+
+// The window the app starts with; closing it quits the app
+pub const MAIN_WINDOW: &str = "main";
+
+// IPC function used by the client script; it never reaches the ProtocolHandler
+const WINDOW_FUNCTION: &str = "__rust_gui_window";
+
+// window.rustWindows.open(label, { url, ...windowConfig }) opens a window, or focuses it if the
+// label is taken. close() without a label closes the calling window. on(type, listener) hears
+// this window's WindowLifecycleEvents; while a close-requested listener is registered, closing
+// waits for the page, and a listener calling event.preventDefault() keeps the window open. The
+// page says so, since that also calls off quitting the app.
+const CLIENT_SCRIPT: &str = r#"
+(() => {
+    const send = (command) => window.ipc?.postMessage(
+        JSON.stringify({ function: '__rust_gui_window', args: [JSON.stringify(command)] })
+    );
+    const listeners = new Map();
+    const label = {label};
+
+    window.addEventListener('rust-event', (e) => {
+        if (e.detail?.event !== 'window') return;
+        const payload = e.detail.payload;
+        let prevented = false;
+        const event = { ...payload, preventDefault: () => { prevented = true; } };
+        for (const listener of listeners.get(payload.type) ?? []) {
+            try {
+                listener(event);
+            } catch (err) {
+                console.error(`Window ${payload.type} listener failed`, err);
+            }
+        }
+        if (payload.type === 'close-requested') {
+            if (prevented) {
+                send({ command: 'close-cancelled', label });
+            } else {
+                window.rustWindows.close();
+            }
+        }
+    });
+
+    window.rustWindows = {
+        label,
+        open(label, options = {}) {
+            send({ ...options, command: 'open', label });
+        },
+        close(target = label) {
+            send({ command: 'close', label: target });
+        },
+        focus(label) {
+            send({ command: 'focus', label });
+        },
+        on(type, listener) {
+            if (!listeners.has(type)) listeners.set(type, new Set());
+            listeners.get(type).add(listener);
+            if (type === 'close-requested') {
+                send({ command: 'guard-close', label, enabled: true });
+            }
+            return () => {
+                const registered = listeners.get(type);
+                registered.delete(listener);
+                if (type === 'close-requested' && registered.size === 0) {
+                    send({ command: 'guard-close', label, enabled: false });
+                }
+            };
+        },
+    };
+})();
+"#;
+
+// Window management requests, from the frontend or from Rust through UserEvent::Window
+#[derive(Debug, Deserialize)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum WindowCommand {
+    Open {
+        label: String,
+        #[serde(flatten)]
+        options: WindowOptions,
+    },
+    Close {
+        label: String,
+    },
+    Focus {
+        label: String,
+    },
+    // Close requests go to the page first while it listens for them. Pages can only guard
+    // their own window, so the label only matters when this comes from Rust.
+    GuardClose {
+        label: String,
+        enabled: bool,
+    },
+    // A listener kept the window open
+    CloseCancelled {
+        label: String,
+    },
+}
+
+// What a page hears about its own window, as a `rust-event` with event 'window'. Sizes and
+// positions are in logical pixels, like the rest of the DOM.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum WindowLifecycleEvent {
+    Focus,
+    Blur,
+    Resize { width: f64, height: f64 },
+    Move { x: f64, y: f64 },
+    ScaleFactorChanged { scale_factor: f64 },
+    ThemeChanged { theme: &'static str },
+    CloseRequested,
+}
+
+// Relative URLs resolve against the main window's start URL, so "/preferences" works with
+// both the dev server and the bundled assets
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowOptions {
+    pub url: Option<String>,
+    #[serde(flatten)]
+    pub window: WindowConfig,
+}
+
+#[derive(Debug)]
+pub enum WindowError {
+    Duplicate(String),
+    InvalidUrl(url::ParseError),
+    ForeignUrl(String),
+    Window(OsError),
+    WebView(wry::Error),
+}
+
+impl fmt::Display for WindowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Duplicate(label) => write!(f, "Window already open: {}", label),
+            Self::InvalidUrl(e) => write!(f, "Invalid window URL: {}", e),
+            Self::ForeignUrl(url) => write!(f, "Window URL is not on the app origin: {}", url),
+            Self::Window(e) => write!(f, "Failed to build window: {}", e),
+            Self::WebView(e) => write!(f, "Failed to build webview: {}", e),
+        }
+    }
+}
+
+impl Error for WindowError {}
+
+impl From<OsError> for WindowError {
+    fn from(e: OsError) -> Self {
+        Self::Window(e)
+    }
+}
+
+impl From<wry::Error> for WindowError {
+    fn from(e: wry::Error) -> Self {
+        Self::WebView(e)
+    }
+}
+
+// Everything every webview is built with, whichever window it lives in
+pub struct WebViewSettings {
+    pub start_url: String,
+    pub security: Arc<SecurityPolicy>,
+    pub schemes: SchemeRegistry,
+    pub protocol_handler: Arc<dyn ProtocolHandler>,
+    // Set up by the IpcServer, if it started
+    pub ipc_client_script: Option<String>,
+}
+
+pub struct ManagedWindow {
+    pub window: Window,
+    pub webview: WebView,
+    remember_state: bool,
+    // Set by the page's IPC handler and cleared whenever a new page starts loading
+    guard_close: Arc<AtomicBool>,
+}
+
+// Labelled windows, each with its own webview, state stash and IPC replies
+pub struct WindowManager {
+    platform: Native,
+    settings: WebViewSettings,
+    proxy: EventLoopProxy<UserEvent>,
+    windows: HashMap<String, ManagedWindow>,
+    labels: HashMap<WindowId, String>,
+    state: WindowStateStore,
+    menu: Option<Menu>,
+    // While quitting, the guarded windows whose pages haven't closed them yet
+    quitting: Option<HashSet<String>>,
+    // The main window stays up until the shutdown hooks have run
+    closing_main: bool,
+}
+
+impl WindowManager {
+    pub fn new(
+        platform: Native,
+        settings: WebViewSettings,
+        proxy: EventLoopProxy<UserEvent>,
+    ) -> Self {
+        Self {
+            platform,
+            settings,
+            proxy,
+            windows: HashMap::new(),
+            labels: HashMap::new(),
+            state: WindowStateStore::load(),
+            menu: None,
+            quitting: None,
+            closing_main: false,
+        }
+    }
+
+    pub fn open(
+        &mut self,
+        target: &EventLoopWindowTarget<UserEvent>,
+        label: &str,
+        config: &WindowConfig,
+        url: Option<&str>,
+    ) -> Result<&ManagedWindow, WindowError> {
+        if self.windows.contains_key(label) {
+            return Err(WindowError::Duplicate(label.to_string()));
+        }
+        let url = match url {
+            Some(url) => resolve_url(&self.settings.start_url, url)?,
+            None => self.settings.start_url.clone(),
+        };
+
+        let builder = if config.remember_state {
+            self.state.restore(label, config, config.builder(), target)
+        } else {
+            config.builder()
+        };
+        let window = builder.build(target)?;
+        self.platform.style_window(&window, config);
+
+        // Replies and stashed state go back to the page that asked for them
+        let (stash, stashed_rx) = StateStash::new();
+        let (tx, rx) = mpsc::channel();
+        let guard_close = Arc::new(AtomicBool::new(false));
+        let builder = self.webview_builder(label, &url, Arc::new(stash), tx, guard_close.clone());
+        let webview = self.platform.build_webview(builder, &window)?;
+        if let Some(menu) = &self.menu {
+            if let Err(e) = self.platform.set_menu(menu, &window) {
+                eprintln!("Failed to set menu on window {}: {}", label, e);
+            }
+        }
+
+        let window_label = label.to_string();
+        events::forward(stashed_rx, self.proxy.clone(), move |()| {
+            UserEvent::StateStashed(window_label.clone())
+        });
+        let window_label = label.to_string();
+        events::forward(rx, self.proxy.clone(), move |script| {
+            UserEvent::EvaluateScript {
+                window: window_label.clone(),
+                script,
+            }
+        });
+
+        self.labels.insert(window.id(), label.to_string());
+        Ok(self
+            .windows
+            .entry(label.to_string())
+            .or_insert(ManagedWindow {
+                window,
+                webview,
+                remember_state: config.remember_state,
+                guard_close,
+            }))
+    }
+
+    // Dropping the webview and window closes them; their state is saved first
+    pub fn close(&mut self, label: &str) -> Option<ManagedWindow> {
+        let managed = self.windows.remove(label)?;
+        self.labels.remove(&managed.window.id());
+        if let Some(pending) = &mut self.quitting {
+            pending.remove(label);
+        }
+        if managed.remember_state {
+            self.state.capture(label, &managed.window);
+            self.state.save();
+        }
+        Some(managed)
+    }
+
+    // Closes a window that may close now. Closing the main window quits the app, which
+    // closes it last.
+    pub fn approve_close(&mut self, label: &str) {
+        if label != MAIN_WINDOW {
+            self.close(label);
+            return;
+        }
+        self.closing_main = true;
+        if let Some(pending) = &mut self.quitting {
+            pending.remove(label);
+        }
+    }
+
+    // Closes every window on the way out, saving their state in one write
+    pub fn close_all(&mut self) {
+        for (label, managed) in self.windows.drain() {
+            if managed.remember_state {
+                self.state.capture(&label, &managed.window);
+            }
+        }
+        self.labels.clear();
+        self.state.save();
+    }
+
+    // Keeps the in-memory state current as the window moves, so it survives maximizing
+    pub fn track(&mut self, id: WindowId) {
+        let Some(label) = self.labels.get(&id) else {
+            return;
+        };
+        if let Some(managed) = self.windows.get(label) {
+            if managed.remember_state && !managed.window.is_maximized() {
+                self.state.capture(label, &managed.window);
+            }
+        }
+    }
+
+    pub fn get(&self, label: &str) -> Option<&ManagedWindow> {
+        self.windows.get(label)
+    }
+
+    pub fn label(&self, id: WindowId) -> Option<&str> {
+        self.labels.get(&id).map(String::as_str)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ManagedWindow)> {
+        self.windows
+            .iter()
+            .map(|(label, managed)| (label.as_str(), managed))
+    }
+
+    // Where menus belong to windows, every window gets the menu bar, so its accelerators work
+    // whichever window has focus. The previous menu, if any, is taken off first.
+    pub fn set_menu(&mut self, menu: &Menu) -> muda::Result<()> {
+        for managed in self.windows.values() {
+            if let Some(old) = &self.menu {
+                self.platform.remove_menu(old, &managed.window)?;
+            }
+            self.platform.set_menu(menu, &managed.window)?;
+        }
+        self.menu = Some(menu.clone());
+        Ok(())
+    }
+
+    // Runs a script in every window, reporting the last failure
+    pub fn evaluate_all(&self, js: &str) -> WryResult<()> {
+        let mut result = Ok(());
+        for managed in self.windows.values() {
+            if let Err(e) = managed.webview.evaluate_script(js) {
+                result = Err(e);
+            }
+        }
+        result
+    }
+
+    pub fn handle(&mut self, target: &EventLoopWindowTarget<UserEvent>, command: WindowCommand) {
+        match command {
+            WindowCommand::Open { label, options } => {
+                if let Some(managed) = self.windows.get(&label) {
+                    managed.window.set_focus();
+                } else if let Err(e) =
+                    self.open(target, &label, &options.window, options.url.as_deref())
+                {
+                    eprintln!("Failed to open window {}: {}", label, e);
+                }
+            }
+            WindowCommand::Close { label } => {
+                if self.windows.contains_key(&label) {
+                    self.approve_close(&label);
+                } else {
+                    eprintln!("Failed to close window {}: not open", label);
+                }
+            }
+            WindowCommand::Focus { label } => match self.windows.get(&label) {
+                Some(managed) => managed.window.set_focus(),
+                None => eprintln!("Failed to focus window {}: not open", label),
+            },
+            WindowCommand::GuardClose { label, enabled } => {
+                if let Some(managed) = self.windows.get_mut(&label) {
+                    managed.guard_close.store(enabled, Ordering::Relaxed);
+                }
+            }
+            WindowCommand::CloseCancelled { label } => {
+                if self.quitting.take().is_some() {
+                    self.closing_main = false;
+                    println!("Quit cancelled by window {}", label);
+                }
+            }
+        }
+    }
+
+    // Whether the app can quit now. Otherwise every guarded window is asked to close first,
+    // and quit_ready() says when they all have. One that stays open calls the quit off.
+    pub fn request_quit(&mut self) -> bool {
+        let pending: HashSet<String> = self
+            .windows
+            .keys()
+            .filter(|label| !self.request_close(label))
+            .cloned()
+            .collect();
+        if pending.is_empty() {
+            self.quitting = None;
+            return true;
+        }
+        self.quitting = Some(pending);
+        false
+    }
+
+    // Whether the app should shut down: the main window is to close, or a quit went ahead
+    pub fn quit_ready(&self) -> bool {
+        match &self.quitting {
+            Some(pending) => pending.is_empty(),
+            None => self.closing_main,
+        }
+    }
+
+    // Whether a close request can go ahead now. A guarded window asks its page instead, which
+    // closes the window itself unless a listener vetoes it.
+    pub fn request_close(&self, label: &str) -> bool {
+        match self.windows.get(label) {
+            // A page that can't be asked can't object either
+            Some(managed) if managed.guard_close.load(Ordering::Relaxed) => {
+                !self.emit(managed, &WindowLifecycleEvent::CloseRequested)
+            }
+            _ => true,
+        }
+    }
+
+    // Passes window events on to the window's own page
+    pub fn notify(&self, id: WindowId, event: &WindowEvent) {
+        let Some(managed) = self.label(id).and_then(|label| self.windows.get(label)) else {
+            return;
+        };
+        let scale = managed.window.scale_factor();
+        let event = match event {
+            WindowEvent::Focused(true) => WindowLifecycleEvent::Focus,
+            WindowEvent::Focused(false) => WindowLifecycleEvent::Blur,
+            WindowEvent::Resized(size) => {
+                let size = size.to_logical::<f64>(scale);
+                WindowLifecycleEvent::Resize {
+                    width: size.width,
+                    height: size.height,
+                }
+            }
+            WindowEvent::Moved(position) => {
+                let position = position.to_logical::<f64>(scale);
+                WindowLifecycleEvent::Move {
+                    x: position.x,
+                    y: position.y,
+                }
+            }
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                WindowLifecycleEvent::ScaleFactorChanged {
+                    scale_factor: *scale_factor,
+                }
+            }
+            WindowEvent::ThemeChanged(theme) => WindowLifecycleEvent::ThemeChanged {
+                theme: match theme {
+                    Theme::Dark => "dark",
+                    _ => "light",
+                },
+            },
+            _ => return,
+        };
+        self.emit(managed, &event);
+    }
+
+    // Whether the script reached the page
+    fn emit(&self, managed: &ManagedWindow, event: &WindowLifecycleEvent) -> bool {
+        let script = events::dispatch_script("window", event);
+        if let Err(e) = managed.webview.evaluate_script(&script) {
+            eprintln!("Failed to send window event: {}", e);
+            return false;
+        }
+        true
+    }
+
+    // Configures the webview; the platform backend attaches it to the window
+    fn webview_builder(
+        &self,
+        label: &str,
+        url: &str,
+        stash: Arc<StateStash>,
+        tx: mpsc::Sender<String>,
+        guard_close: Arc<AtomicBool>,
+    ) -> WebViewBuilder<'static> {
+        let settings = &self.settings;
+        let tx = Arc::new(tx);
+        let protocol_handler = settings.protocol_handler.clone();
+        let proxy = self.proxy.clone();
+        let start_url = Url::parse(&settings.start_url).ok();
+        let page_guard = guard_close.clone();
+
+        let builder = WebViewBuilder::new()
+            .with_initialization_script(&format!(
+                "
+            document.addEventListener('DOMContentLoaded', () => {{
+                const style = document.createElement('style');
+                style.nonce = '{}';
+                style.textContent = `{}`;
+                document.head.appendChild(style);
+            }});
+        ",
+                settings.security.nonce(),
+                r#"
+                * {
+                    cursor: default !important;
+                    -webkit-user-select: none;
+                    -moz-user-select: none;
+                    -ms-user-select: none;
+                    user-select: none;
+                }
+            "#
+            ))
+            .with_url(url)
+            .with_initialization_script(state_stash::CLIENT_SCRIPT)
+            .with_initialization_script(&client_script(label))
+            .with_initialization_script(menu::CLIENT_SCRIPT)
+            .with_ipc_handler(move |req| {
+                // The request URI is the page that sent it; other sites can't drive the app
+                let from_app = Url::parse(&req.uri().to_string()).is_ok_and(|page| {
+                    start_url
+                        .as_ref()
+                        .is_some_and(|start| on_app_origin(&page, start))
+                });
+                if let Some(command) = ipc::command(req.body(), WINDOW_FUNCTION) {
+                    if !from_app {
+                        eprintln!("Ignored window command from {}", req.uri());
+                    } else if let WindowCommand::GuardClose { enabled, .. } = command {
+                        // A page only guards its own window, whatever label it names
+                        guard_close.store(enabled, Ordering::Relaxed);
+                    } else {
+                        let _ = proxy.send_event(UserEvent::Window(command));
+                    }
+                    return;
+                }
+                if let Some(command) = menu::command(req.body()) {
+                    if from_app {
+                        let _ = proxy.send_event(UserEvent::Menu(command));
+                    } else {
+                        eprintln!("Ignored menu command from {}", req.uri());
+                    }
+                    return;
+                }
+                if stash.intercept(req.body(), &tx) {
+                    return;
+                }
+                let tx = tx.clone();
+                let handler = protocol_handler.clone();
+                ipc::handle_ipc_message(req.body(), tx, handler);
+            })
+            // A fresh page, after a navigation, reload or hot reload, has no close-requested
+            // listener until it registers one again
+            .with_on_page_load_handler(move |event, _| {
+                if let PageLoadEvent::Started = event {
+                    page_guard.store(false, Ordering::Relaxed);
+                }
+            })
+            .with_initialization_script(
+                r#"
+            // Enable HMR support detection
+            window.__HMR_ENABLED__ = true;
+        "#,
+            );
+        let builder = match &settings.ipc_client_script {
+            Some(script) => builder.with_initialization_script(script),
+            None => builder,
+        };
+        settings.schemes.attach(builder, &settings.security)
+    }
+}
+
+fn client_script(label: &str) -> String {
+    CLIENT_SCRIPT.replace("{label}", &serde_json::to_string(label).unwrap_or_default())
+}
+
+// Resolves a window URL against the start page. Only pages of the app itself may be opened,
+// since every window gets the IPC bridge.
+fn resolve_url(start_url: &str, url: &str) -> Result<String, WindowError> {
+    let start = Url::parse(start_url).map_err(WindowError::InvalidUrl)?;
+    let resolved = start.join(url).map_err(WindowError::InvalidUrl)?;
+    if on_app_origin(&resolved, &start) {
+        Ok(resolved.to_string())
+    } else {
+        Err(WindowError::ForeignUrl(resolved.to_string()))
+    }
+}
+
+// application:// pages, or the origin the app starts on, such as the Vite dev server.
+// Custom-scheme origins are opaque, so those are matched by scheme.
+fn on_app_origin(url: &Url, start: &Url) -> bool {
+    url.scheme() == "application" || (url.origin().is_tuple() && url.origin() == start.origin())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::APP_URL;
+
+    #[test]
+    fn resolves_routes_against_the_app_url() {
+        assert_eq!(
+            resolve_url(APP_URL, "/preferences").unwrap(),
+            "application://index.html/preferences"
+        );
+        assert_eq!(
+            resolve_url(APP_URL, "settings/profile?tab=2").unwrap(),
+            "application://index.html/settings/profile?tab=2"
+        );
+        assert_eq!(
+            resolve_url(APP_URL, "application://index.html/about").unwrap(),
+            "application://index.html/about"
+        );
+        assert!(matches!(
+            resolve_url(APP_URL, "https://example.com/"),
+            Err(WindowError::ForeignUrl(_))
+        ));
+        assert!(matches!(
+            resolve_url(APP_URL, "assets://assets/index.js"),
+            Err(WindowError::ForeignUrl(_))
+        ));
+    }
+
+    #[test]
+    fn resolves_relative_urls_against_the_start_page() {
+        assert_eq!(
+            resolve_url("application://localhost/index.html", "/preferences").unwrap(),
+            "application://localhost/preferences"
+        );
+        assert_eq!(
+            resolve_url("http://localhost:5173/", "settings?tab=1").unwrap(),
+            "http://localhost:5173/settings?tab=1"
+        );
+    }
+
+    #[test]
+    fn accepts_absolute_urls_on_the_app_origin() {
+        assert!(resolve_url("http://localhost:5173/", "http://localhost:5173/about").is_ok());
+        assert!(resolve_url("http://localhost:5173/", "application://localhost/about").is_ok());
+    }
+
+    #[test]
+    fn rejects_other_origins() {
+        for url in [
+            "https://example.com/",
+            "//example.com/",
+            "http://localhost:8080/",
+            "assets://localhost/page.html",
+        ] {
+            assert!(
+                matches!(
+                    resolve_url("http://localhost:5173/", url),
+                    Err(WindowError::ForeignUrl(_))
+                ),
+                "{}",
+                url
+            );
+        }
+        assert!(matches!(
+            resolve_url("application://localhost/", "https://example.com/"),
+            Err(WindowError::ForeignUrl(_))
+        ));
+    }
+}